@@ -0,0 +1,66 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::de::DeserializeSeed;
+use wasmtime_component_serde::{DeserializeValWith, Options, TypePlan};
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+fn list_of_records(c: &mut Criterion) {
+    let ty = common::component_type(
+        r#"
+        (type $v0 (variant (case "none") (case "some" u32) (case "many" (list u32))))
+        (export $v "v" (type $v0))
+        (type $r0 (record
+            (field "id" u64)
+            (field "name" string)
+            (field "score" float64)
+            (field "tag" (option string))
+            (field "kind" $v)
+        ))
+        (export $r "r" (type $r0))
+        "#,
+        "(list $r)",
+    );
+    let plan = TypePlan::new(&ty);
+    let options = Options::new();
+
+    let mut group = c.benchmark_group("list<record>");
+    for len in [100, 10_000] {
+        let json = serde_json::to_vec(&serde_json::Value::Array(
+            (0..len)
+                .map(|i| {
+                    serde_json::json!({
+                        "id": i,
+                        "name": format!("record-{i}"),
+                        "score": i as f64 / 3.0,
+                        "tag": (i % 2 == 0).then_some("even"),
+                        "kind": {"some": i},
+                    })
+                })
+                .collect(),
+        ))
+        .unwrap();
+        group.throughput(Throughput::Elements(len));
+
+        group.bench_with_input(BenchmarkId::new("DeserializeVal", len), &json, |b, json| {
+            b.iter(|| {
+                let mut d = serde_json::Deserializer::from_slice(json);
+                DeserializeValWith::new(&ty, &options)
+                    .deserialize(&mut d)
+                    .unwrap()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("TypePlan", len), &json, |b, json| {
+            b.iter(|| {
+                let mut d = serde_json::Deserializer::from_slice(json);
+                DeserializeValWith::with_plan(&plan, &options)
+                    .deserialize(&mut d)
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, list_of_records);
+criterion_main!(benches);
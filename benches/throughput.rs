@@ -0,0 +1,138 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::de::DeserializeSeed;
+use serde_json::{json, Value};
+use wasmtime::component::{Type, Val};
+use wasmtime_component_serde::{from_json, DeserializeVal, SerializeVal};
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+struct Fixture {
+    name: &'static str,
+    ty: Type,
+    val: Val,
+}
+
+impl Fixture {
+    fn new(name: &'static str, defs: &str, ty: &str, json: Value) -> Self {
+        let ty = common::component_type(defs, ty);
+        let val = from_json(&ty, json.to_string()).expect(name);
+        Self { name, ty, val }
+    }
+}
+
+fn fixtures() -> Vec<Fixture> {
+    const DEPTH: usize = 16;
+    let mut deep_defs =
+        r#"(type $t0 (record (field "depth" u32))) (export $r0 "r0" (type $t0))"#.to_string();
+    for i in 1..DEPTH {
+        deep_defs += &format!(
+            r#"(type $t{i} (record (field "depth" u32) (field "next" $r{prev})))
+            (export $r{i} "r{i}" (type $t{i}))"#,
+            prev = i - 1
+        );
+    }
+    let mut deep_json = json!({"depth": 0});
+    for i in 1..DEPTH {
+        deep_json = json!({"depth": i, "next": deep_json});
+    }
+
+    let wide_cases = (0..64)
+        .map(|i| format!(r#"(case "c{i}" u32)"#))
+        .collect::<String>();
+
+    vec![
+        Fixture::new(
+            "list<u8>",
+            "",
+            "(list u8)",
+            json!((0..65536).map(|i| i as u8).collect::<Vec<_>>()),
+        ),
+        Fixture::new(
+            "list<string>",
+            "",
+            "(list string)",
+            json!((0..1000).map(|i| format!("{i:032}")).collect::<Vec<_>>()),
+        ),
+        Fixture::new(
+            "deep record",
+            &deep_defs,
+            &format!("(list $r{})", DEPTH - 1),
+            json!(vec![deep_json; 100]),
+        ),
+        Fixture::new(
+            "wide variant",
+            &format!(r#"(type $t (variant {wide_cases})) (export $v "v" (type $t))"#),
+            "(list $v)",
+            Value::Array(
+                (0..1000)
+                    .map(|i| json!({ format!("c{}", i % 64): i }))
+                    .collect(),
+            ),
+        ),
+        Fixture::new(
+            "nested options",
+            "",
+            "(list (option (option (option u32))))",
+            Value::Array(
+                (0..1000)
+                    .map(|i| match i % 4 {
+                        0 => json!(null),
+                        1 => json!({"value": null}),
+                        2 => json!({"value": {"value": null}}),
+                        _ => json!({"value": {"value": i}}),
+                    })
+                    .collect(),
+            ),
+        ),
+    ]
+}
+
+fn serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialize");
+    for fixture in fixtures() {
+        let val = &fixture.val;
+        let json = serde_json::to_vec(&SerializeVal(val)).unwrap();
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_with_input(BenchmarkId::new("json", fixture.name), val, |b, val| {
+            b.iter(|| serde_json::to_vec(&SerializeVal(val)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("msgpack", fixture.name), val, |b, val| {
+            b.iter(|| rmp_serde::to_vec(&SerializeVal(val)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn deserialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deserialize");
+    for fixture in fixtures() {
+        let ty = &fixture.ty;
+        let json = serde_json::to_vec(&SerializeVal(&fixture.val)).unwrap();
+        let msgpack = rmp_serde::to_vec(&SerializeVal(&fixture.val)).unwrap();
+
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_with_input(BenchmarkId::new("json", fixture.name), &json, |b, json| {
+            b.iter(|| {
+                let mut d = serde_json::Deserializer::from_slice(json);
+                DeserializeVal(ty).deserialize(&mut d).unwrap()
+            })
+        });
+
+        group.throughput(Throughput::Bytes(msgpack.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("msgpack", fixture.name),
+            &msgpack,
+            |b, msgpack| {
+                b.iter(|| {
+                    let mut d = rmp_serde::Deserializer::new(&msgpack[..]);
+                    DeserializeVal(ty).deserialize(&mut d).unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, serialize, deserialize);
+criterion_main!(benches);
@@ -0,0 +1,83 @@
+#![no_main]
+
+use std::sync::OnceLock;
+
+use libfuzzer_sys::fuzz_target;
+use wasmtime::component::Type;
+use wasmtime_component_serde::{from_json_with, to_json, Limits, Options};
+
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+fn types() -> &'static [Type] {
+    static TYPES: OnceLock<Vec<Type>> = OnceLock::new();
+    TYPES.get_or_init(|| {
+        vec![
+            // Recursively nested records
+            common::component_type(
+                r#"
+                (type $t0 (record (field "leaf" u8) (field "tag" (option string))))
+                (export $r0 "r0" (type $t0))
+                (type $t1 (record (field "depth" u32) (field "next" (option $r0)) (field "items" (list $r0))))
+                (export $r1 "r1" (type $t1))
+                (type $t2 (record (field "next" $r1) (field "items" (list $r1))))
+                (export $r2 "r2" (type $t2))
+                "#,
+                "$r2",
+            ),
+            // Nested variants
+            common::component_type(
+                r#"
+                (type $t0 (variant (case "a") (case "b" u32) (case "c" string)))
+                (export $v0 "v0" (type $t0))
+                (type $t1 (variant (case "leaf" $v0) (case "list" (list $v0)) (case "opt" (option $v0))))
+                (export $v1 "v1" (type $t1))
+                "#,
+                "(list $v1)",
+            ),
+            // Nested options
+            common::component_type(
+                "",
+                "(option (option (option (list (option (option u8))))))",
+            ),
+            // Everything else
+            common::component_type(
+                r#"
+                (type $t0 (flags "a" "b" "c")) (export $f "f" (type $t0))
+                (type $t1 (enum "x" "y")) (export $e "e" (type $t1))
+                "#,
+                "(tuple (result u8 (error string)) (list char) float32 float64 s64 $f $e)",
+            ),
+        ]
+    })
+}
+
+fn options() -> &'static Options {
+    static OPTIONS: OnceLock<Options> = OnceLock::new();
+    OPTIONS.get_or_init(|| {
+        Options::new().limits(Limits {
+            max_depth: 64,
+            max_list_len: 1 << 16,
+            max_string_len: 1 << 16,
+            max_values: 1 << 16,
+            max_bytes: 1 << 20,
+            ..Limits::default()
+        })
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((selector, json)) = data.split_first() else {
+        return;
+    };
+    let types = types();
+    let ty = &types[*selector as usize % types.len()];
+
+    let Ok(val) = from_json_with(ty, json, options()) else {
+        return;
+    };
+    let reserialized = to_json(&val).expect("accepted value should serialize");
+    let reparsed = from_json_with(ty, &reserialized, options())
+        .unwrap_or_else(|err| panic!("{reserialized} should parse again: {err}"));
+    assert_eq!(reparsed, val, "{reserialized}");
+});
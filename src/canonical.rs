@@ -0,0 +1,148 @@
+use std::{fmt::LowerExp, io};
+
+use serde::Serialize;
+use serde_json::ser::Formatter;
+use wasmtime::component::Val;
+
+use crate::{Options, SerializeValWith};
+
+/// Serialize a [`Val`] to canonical JSON, compatible with the
+/// [JSON Canonicalization Scheme](https://www.rfc-editor.org/rfc/rfc8785)
+/// (JCS).
+///
+/// Equal values always produce identical bytes:
+/// - there is no whitespace
+/// - object keys are sorted by their UTF-16 code units
+/// - floats use the ECMAScript number format (e.g. `1e+21`, `0.000001`, and
+///   `0` for `-0`)
+/// - `flags` are listed in declaration order
+/// - `none` record fields are omitted
+///
+/// Integers are written exactly; JCS implementations may only reproduce those
+/// within ±2<sup>53</sup>.
+pub fn to_canonical_json(val: &Val) -> serde_json::Result<String> {
+    let mut json = Vec::new();
+    to_canonical_json_writer(&mut json, val)?;
+    // The serializer only writes valid UTF-8.
+    Ok(String::from_utf8(json).unwrap())
+}
+
+/// Serialize a [`Val`] as [canonical JSON](to_canonical_json) into the given
+/// writer.
+pub fn to_canonical_json_writer(writer: impl io::Write, val: &Val) -> serde_json::Result<()> {
+    let options = Options {
+        canonical: true,
+        ..Options::default()
+    };
+    let mut serializer = serde_json::Serializer::with_formatter(writer, CanonicalFormatter);
+    SerializeValWith::new(val, &options).serialize(&mut serializer)
+}
+
+/// Returns the SHA-256 hash of the [canonical JSON](to_canonical_json) of a
+/// [`Val`].
+#[cfg(feature = "sha2")]
+pub fn canonical_hash(val: &Val) -> serde_json::Result<[u8; 32]> {
+    use sha2::{Digest, Sha256};
+
+    Ok(Sha256::digest(to_canonical_json(val)?).into())
+}
+
+struct CanonicalFormatter;
+
+impl Formatter for CanonicalFormatter {
+    fn write_f32<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f32) -> io::Result<()> {
+        writer.write_all(es_number(value).as_bytes())
+    }
+
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        writer.write_all(es_number(value).as_bytes())
+    }
+}
+
+/// Formats a finite float like ECMAScript's `Number.prototype.toString`.
+fn es_number(value: impl LowerExp) -> String {
+    // `{:e}` gives the shortest digits that round-trip, e.g. `-1.25e-7`.
+    let sci = format!("{value:e}");
+    let (negative, sci) = match sci.strip_prefix('-') {
+        Some(sci) => (true, sci),
+        None => (false, sci.as_str()),
+    };
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    if digits == "0" {
+        return "0".into();
+    }
+    let k = digits.len() as i32;
+    let n = exp.parse::<i32>().unwrap() + 1;
+
+    let mut out = String::new();
+    if negative {
+        out.push('-');
+    }
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.extend(std::iter::repeat_n('0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        out.push_str(int);
+        out.push('.');
+        out.push_str(frac);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', -n as usize));
+        out.push_str(&digits);
+    } else {
+        let (first, rest) = digits.split_at(1);
+        out.push_str(first);
+        if !rest.is_empty() {
+            out.push('.');
+            out.push_str(rest);
+        }
+        let e = n - 1;
+        out.push_str(if e > 0 { "e+" } else { "e-" });
+        out.push_str(&e.abs().to_string());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn es_numbers() {
+        for (value, expected) in [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (0.002, "0.002"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (123.456, "123.456"),
+            (1e20, "100000000000000000000"),
+            (1e21, "1e+21"),
+            (-1.2345e-30, "-1.2345e-30"),
+            (f64::MAX, "1.7976931348623157e+308"),
+            (f64::MIN_POSITIVE, "2.2250738585072014e-308"),
+            (123456789012345680000.0, "123456789012345680000"),
+        ] {
+            assert_eq!(es_number(value), expected, "{value:?}");
+        }
+        assert_eq!(es_number(1.1f32), "1.1");
+    }
+
+    #[test]
+    fn canonical_numbers_and_strings() {
+        assert_eq!(to_canonical_json(&Val::Float64(-0.0)).unwrap(), "0");
+        assert_eq!(to_canonical_json(&Val::Float64(1e21)).unwrap(), "1e+21");
+        assert_eq!(
+            to_canonical_json(&Val::Float64(f64::NAN)).unwrap(),
+            r#""NaN""#
+        );
+        assert_eq!(
+            to_canonical_json(&Val::String("\u{1}\"é/".into())).unwrap(),
+            r#""\u0001\"é/""#
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use std::{fmt, sync::Arc};
+
+use serde_json::Value;
+use wasmtime::component::{Type, Val};
+
+use crate::TypeDescriptor;
+
+/// A custom JSON mapping for a specific type, registered in a
+/// [`CodecRegistry`].
+///
+/// For example, a `datetime` record could be mapped to an RFC 3339 string.
+pub trait Codec: Send + Sync {
+    /// Encodes a [`Val`] as JSON.
+    fn encode(&self, val: &Val) -> wasmtime::Result<Value>;
+
+    /// Decodes a [`Val`] of the given [`Type`] from JSON.
+    fn decode(&self, ty: &Type, json: Value) -> wasmtime::Result<Val>;
+}
+
+/// A set of [`Codec`]s, consulted by [`SerializeVal`](crate::SerializeVal)
+/// and [`DeserializeVal`](crate::DeserializeVal) for every value when set
+/// with [`Options::codecs`](crate::Options::codecs).
+///
+/// Codecs are matched in registration order. Deserializing a value with a
+/// codec requires a self-describing format (e.g. JSON).
+///
+/// A value handled by a codec is read in full as a [`Value`] before being
+/// decoded, so [`Limits`](crate::Limits) other than `max_depth` and
+/// `max_values` (which count it as a single value) don't apply within it.
+/// Avoid codecs for large or deeply nested types in untrusted input.
+#[derive(Clone, Default)]
+pub struct CodecRegistry {
+    codecs: Vec<(Matcher, Arc<dyn Codec>)>,
+}
+
+#[derive(Clone)]
+enum Matcher {
+    Descriptor(TypeDescriptor),
+    Type(Type),
+}
+
+impl CodecRegistry {
+    /// Returns an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a codec for every type matching the given descriptor, e.g.
+    /// any `record { seconds: u64, nanoseconds: u32 }`.
+    pub fn register(mut self, descriptor: TypeDescriptor, codec: impl Codec + 'static) -> Self {
+        self.codecs
+            .push((Matcher::Descriptor(descriptor), Arc::new(codec)));
+        self
+    }
+
+    /// Registers a codec for a specific [`Type`], e.g. a named type taken
+    /// from a component's exports. Structurally identical types declared
+    /// elsewhere aren't matched.
+    pub fn register_type(mut self, ty: Type, codec: impl Codec + 'static) -> Self {
+        self.codecs.push((Matcher::Type(ty), Arc::new(codec)));
+        self
+    }
+
+    pub(crate) fn find(&self, ty: &Type) -> Option<&dyn Codec> {
+        self.codecs.iter().find_map(|(matcher, codec)| {
+            let matches = match matcher {
+                Matcher::Descriptor(desc) => desc.describes(ty),
+                Matcher::Type(t) => t == ty,
+            };
+            matches.then_some(codec.as_ref())
+        })
+    }
+}
+
+impl fmt::Debug for CodecRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CodecRegistry")
+            .field("len", &self.codecs.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{from_json_with, to_json_with, Options};
+
+    /// Encodes `bool`s as `"yes"` or `"no"`.
+    struct YesNo;
+
+    impl Codec for YesNo {
+        fn encode(&self, val: &Val) -> wasmtime::Result<Value> {
+            match val {
+                Val::Bool(b) => Ok(json!(if *b { "yes" } else { "no" })),
+                _ => Err(wasmtime::Error::msg("expected a bool")),
+            }
+        }
+
+        fn decode(&self, _ty: &Type, json: Value) -> wasmtime::Result<Val> {
+            match json.as_str() {
+                Some("yes") => Ok(Val::Bool(true)),
+                Some("no") => Ok(Val::Bool(false)),
+                _ => Err(wasmtime::Error::msg("expected \"yes\" or \"no\"")),
+            }
+        }
+    }
+
+    #[test]
+    fn codec_replaces_mapping_for_matching_types() {
+        let options =
+            Options::new().codecs(CodecRegistry::new().register(TypeDescriptor::Bool, YesNo));
+        assert_eq!(
+            to_json_with(&Val::Bool(true), &options).unwrap(),
+            r#""yes""#
+        );
+        assert_eq!(
+            from_json_with(&Type::Bool, r#""no""#, &options).unwrap(),
+            Val::Bool(false)
+        );
+        assert!(from_json_with(&Type::Bool, "false", &options).is_err());
+        assert_eq!(to_json_with(&Val::U8(1), &options).unwrap(), "1");
+    }
+}
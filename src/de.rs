@@ -1,13 +1,33 @@
-use std::collections::HashMap;
+use std::{
+    cell::Cell,
+    collections::{hash_map::Entry, HashMap},
+};
 
 use serde::{
-    de::{self, DeserializeSeed, Unexpected, Visitor},
+    de::{
+        self,
+        value::{BytesDeserializer, MapAccessDeserializer, SeqAccessDeserializer},
+        DeserializeSeed, IgnoredAny, IntoDeserializer, Unexpected, Visitor,
+    },
     Deserializer,
 };
-use wasmtime::component::{Type, Val};
+use wasmtime::component::{types, Type, Val};
+
+use crate::{
+    options::{default_options, is_map_list},
+    plan::{PlanKind, PlanNode},
+    suggest::unknown_name,
+    CharRepr, DuplicateKeys, FlagsRepr, LimitExceeded, LimitKind, Options, TypePlan,
+};
+
+/// The maximum number of elements to preallocate based on a size hint.
+const MAX_PREALLOC_LEN: usize = 4096;
 
 /// A [`serde::de::DeserializeSeed`] implementation for deserializing [`Val`]s
 /// of a given dynamic [`Type`].
+///
+/// See [`DeserializeValWith`] for non-default [`Options`].
+#[derive(Clone, Copy)]
 pub struct DeserializeVal<'a>(pub &'a Type);
 
 impl<'a, 'de> DeserializeSeed<'de> for DeserializeVal<'a> {
@@ -17,39 +37,388 @@ impl<'a, 'de> DeserializeSeed<'de> for DeserializeVal<'a> {
     where
         D: serde::Deserializer<'de>,
     {
-        match self.0 {
+        DeserializeValWith::new(self.0, default_options()).deserialize(deserializer)
+    }
+}
+
+/// Like [`DeserializeVal`], with the given [`Options`] and optionally a
+/// [`TypePlan`].
+#[derive(Clone, Copy)]
+pub struct DeserializeValWith<'a> {
+    ty: &'a Type,
+    options: &'a Options,
+    depth: usize,
+    budget: Option<&'a Budget>,
+    plan: Option<&'a PlanNode>,
+}
+
+/// Running totals checked against [`Limits`](crate::Limits) for a single
+/// top-level deserialization.
+#[derive(Default)]
+struct Budget {
+    values: Cell<usize>,
+    bytes: Cell<usize>,
+}
+
+impl<'a> DeserializeValWith<'a> {
+    /// Deserializes a [`Val`] of the given [`Type`] with the given [`Options`].
+    pub fn new(ty: &'a Type, options: &'a Options) -> Self {
+        Self {
+            ty,
+            options,
+            depth: 0,
+            budget: None,
+            plan: None,
+        }
+    }
+
+    /// Deserializes a [`Val`] of the [`Type`] a [`TypePlan`] was built for
+    /// with the given [`Options`].
+    pub fn with_plan(plan: &'a TypePlan, options: &'a Options) -> Self {
+        let root = plan.root();
+        Self {
+            ty: &root.ty,
+            options,
+            depth: 0,
+            budget: None,
+            plan: Some(root),
+        }
+    }
+
+    fn child<'b>(&self, ty: &'b Type) -> DeserializeValWith<'b>
+    where
+        'a: 'b,
+    {
+        DeserializeValWith {
+            ty,
+            options: self.options,
+            depth: self.depth + 1,
+            budget: self.budget,
+            plan: None,
+        }
+    }
+
+    fn planned_child(&self, node: &'a PlanNode) -> DeserializeValWith<'a> {
+        DeserializeValWith {
+            ty: &node.ty,
+            options: self.options,
+            depth: self.depth + 1,
+            budget: self.budget,
+            plan: Some(node),
+        }
+    }
+
+    fn plan_kind(&self) -> Option<&'a PlanKind> {
+        self.plan.map(|node| &node.kind)
+    }
+
+    fn child_maybe<'b>(&self, ty: Option<&'b Type>) -> Option<DeserializeValWith<'b>>
+    where
+        'a: 'b,
+    {
+        ty.map(|ty| self.child(ty))
+    }
+
+    /// Checks the depth and value count limits for this value.
+    fn enter<E: de::Error>(&self) -> Result<(), E> {
+        let max_depth = self.options.limits.max_depth;
+        if self.depth >= max_depth {
+            return Err(limit_exceeded(LimitKind::Depth, max_depth));
+        }
+        self.count_values(1)
+    }
+
+    /// Returns true if a registered codec handles this value.
+    fn has_codec(&self) -> bool {
+        #[cfg(feature = "json")]
+        {
+            self.options.codec_for(self.ty).is_some()
+        }
+        #[cfg(not(feature = "json"))]
+        {
+            false
+        }
+    }
+
+    fn count_values<E: de::Error>(&self, n: usize) -> Result<(), E> {
+        let limit = self.options.limits.max_values;
+        if let Some(budget) = self.budget {
+            let total = budget.values.get().saturating_add(n);
+            if total > limit {
+                return Err(limit_exceeded(LimitKind::Values, limit));
+            }
+            budget.values.set(total);
+        }
+        Ok(())
+    }
+
+    fn count_bytes<E: de::Error>(&self, n: usize) -> Result<(), E> {
+        let limit = self.options.limits.max_bytes;
+        if let Some(budget) = self.budget {
+            let total = budget.bytes.get().saturating_add(n);
+            if total > limit {
+                return Err(limit_exceeded(LimitKind::Bytes, limit));
+            }
+            budget.bytes.set(total);
+        }
+        Ok(())
+    }
+
+    fn check_string_len<E: de::Error>(&self, len: usize) -> Result<(), E> {
+        let limit = self.options.limits.max_string_len;
+        if len > limit {
+            return Err(limit_exceeded(LimitKind::StringLength, limit));
+        }
+        self.count_bytes(len)
+    }
+
+    fn check_list_len<E: de::Error>(&self, len: usize) -> Result<(), E> {
+        let limit = self.options.limits.max_list_len;
+        if len > limit {
+            return Err(limit_exceeded(LimitKind::ListLength, limit));
+        }
+        Ok(())
+    }
+
+    fn prealloc_len(&self, size_hint: Option<usize>) -> usize {
+        size_hint
+            .unwrap_or_default()
+            .min(self.options.limits.max_list_len)
+            .min(MAX_PREALLOC_LEN)
+    }
+
+    fn resolve_name<'n, E: de::Error>(
+        &self,
+        name: &'n str,
+        declared: impl IntoIterator<Item = &'n str>,
+    ) -> Result<&'n str, E> {
+        self.options
+            .resolve_name(name, declared)
+            .map_err(de::Error::custom)
+    }
+
+    fn resolve_case<'n, E: de::Error>(
+        &self,
+        name: &'n str,
+        declared: impl IntoIterator<Item = &'n str>,
+    ) -> Result<&'n str, E> {
+        self.options
+            .resolve_case(name, declared)
+            .map_err(de::Error::custom)
+    }
+
+    /// Deserializes a `list<tuple<string, T>>` from a map.
+    fn visit_map_list<'de, A>(self, list: &types::List, mut map: A) -> Result<Val, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let entry_ty = list.ty();
+        let Type::Tuple(tuple) = &entry_ty else {
+            unreachable!("checked by is_map_list");
+        };
+        let value_ty = tuple.types().nth(1).unwrap();
+        let entry = self.child(&entry_ty);
+        let value = match self.plan_kind() {
+            Some(PlanKind::List(node)) => match &node.kind {
+                PlanKind::Tuple(nodes) => self.planned_child(node).planned_child(&nodes[1]),
+                _ => entry.child(&value_ty),
+            },
+            _ => entry.child(&value_ty),
+        };
+
+        let mut index = HashMap::new();
+        let mut entries = Vec::with_capacity(self.prealloc_len(map.size_hint()));
+        while let Some(key) = map.next_key::<String>()? {
+            // The entry tuple and its key
+            entry.count_values(2)?;
+            entry.check_string_len(key.len())?;
+            let v = map.next_value_seed(value)?;
+            let new_entry = tuple
+                .new_val(Box::new([Val::String(key.as_str().into()), v]))
+                .map_err(de::Error::custom)?;
+            match index.entry(key) {
+                Entry::Vacant(e) => {
+                    self.check_list_len(entries.len() + 1)?;
+                    e.insert(entries.len());
+                    entries.push(new_entry);
+                }
+                Entry::Occupied(e) => match self.options.duplicate_keys {
+                    DuplicateKeys::Error => {
+                        return Err(de::Error::custom(format!("duplicate key `{}`", e.key())))
+                    }
+                    DuplicateKeys::First => (),
+                    DuplicateKeys::Last => entries[*e.get()] = new_entry,
+                },
+            }
+        }
+        list.new_val(entries.into()).map_err(de::Error::custom)
+    }
+
+    /// Converts an integer to a float type, rejecting integers that can't be
+    /// represented exactly.
+    fn int_to_float<E: de::Error>(self, v: i128, unexpected: Unexpected) -> Result<Val, E> {
+        match self.ty {
+            Type::Float32 if v as f32 as i128 == v => Ok(Val::Float32(v as f32)),
+            Type::Float64 if v as f64 as i128 == v => Ok(Val::Float64(v as f64)),
+            _ => Err(de::Error::invalid_value(unexpected, &self)),
+        }
+    }
+
+    /// Converts an integral float to an integer type, rejecting out of range
+    /// values.
+    fn float_to_int<E: de::Error>(self, v: f64) -> Result<Val, E> {
+        // Both bounds are exactly representable as f64.
+        let in_range = v.fract() == 0.0 && v >= i64::MIN as f64 && v < u64::MAX as f64 + 1.0;
+        if !in_range {
+            return Err(de::Error::invalid_value(Unexpected::Float(v), &self));
+        }
+        let result = if v < 0.0 {
+            self.visit_i64::<E>(v as i64)
+        } else {
+            self.visit_u64::<E>(v as u64)
+        };
+        result.map_err(|_| de::Error::invalid_value(Unexpected::Float(v), &self))
+    }
+
+    /// Converts an f64 to a `float32`, checking for precision loss if
+    /// [`Options::strict_float32`] is set.
+    fn f64_to_float32<E: de::Error>(self, v: f64) -> Result<Val, E> {
+        let f = v as f32;
+        // Accept values that are exactly representable as well as the
+        // shortest decimal form of an f32 (e.g. `1.1`), which is how
+        // `float32`s are serialized.
+        if self.options.strict_float32
+            && v.is_finite()
+            && f as f64 != v
+            && f.to_string().parse::<f64>() != Ok(v)
+        {
+            return Err(de::Error::invalid_value(Unexpected::Float(v), &self));
+        }
+        Ok(Val::Float32(f))
+    }
+}
+
+/// Parses the bit pattern of a NaN with a payload, e.g. `"NaN:0x7fc00001"`.
+fn nan_bits(v: &str) -> Option<u64> {
+    let hex = v.strip_prefix("NaN:0x")?;
+    if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(hex, 16).ok()
+}
+
+/// Parses an integer per [`Options::extended_int_syntax`], e.g. `"-0xff"` or
+/// `"1_000"`. Separators must be between digits.
+fn parse_extended_int(v: &str) -> Option<i128> {
+    let (negative, v) = match v.strip_prefix('-') {
+        Some(v) => (true, v),
+        None => (false, v.strip_prefix('+').unwrap_or(v)),
+    };
+    let (radix, digits) = match v.get(..2) {
+        Some("0x" | "0X") => (16, &v[2..]),
+        Some("0o" | "0O") => (8, &v[2..]),
+        Some("0b" | "0B") => (2, &v[2..]),
+        _ => (10, v),
+    };
+    if digits.is_empty()
+        || digits.starts_with('_')
+        || digits.ends_with('_')
+        || digits.contains("__")
+    {
+        return None;
+    }
+    let digits = digits.replace('_', "");
+    if !digits.bytes().all(|b| (b as char).is_digit(radix)) {
+        return None;
+    }
+    let v = i128::from_str_radix(&digits, radix).ok()?;
+    Some(if negative { -v } else { v })
+}
+
+fn limit_exceeded<E: de::Error>(kind: LimitKind, limit: usize) -> E {
+    E::custom(LimitExceeded { kind, limit })
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for DeserializeValWith<'a> {
+    type Value = Val;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        if self.budget.is_none() {
+            let budget = Budget::default();
+            return DeserializeValWith {
+                ty: self.ty,
+                options: self.options,
+                depth: self.depth,
+                budget: Some(&budget),
+                plan: self.plan,
+            }
+            .deserialize(deserializer);
+        }
+        self.enter()?;
+
+        #[cfg(feature = "json")]
+        if let Some(codec) = self.options.codec_for(self.ty) {
+            let json = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+            return codec.decode(self.ty, json).map_err(de::Error::custom);
+        }
+
+        match self.ty {
             Type::Bool => deserializer.deserialize_bool(self),
-            Type::Char => deserializer.deserialize_char(self),
+            Type::Char => match self.options.chars {
+                CharRepr::String => deserializer.deserialize_char(self),
+                CharRepr::CodePoint => deserializer.deserialize_u32(self),
+            },
             Type::String => deserializer.deserialize_string(self),
+            // Map-like lists may also be written as arrays of pairs.
+            Type::List(list) if self.options.map_lists && is_map_list(list) => {
+                deserializer.deserialize_any(self)
+            }
             Type::List(_) => deserializer.deserialize_seq(self),
             Type::Record(_) => deserializer.deserialize_map(self),
             Type::Tuple(tuple) => deserializer.deserialize_tuple(tuple.types().len(), self),
             Type::Variant(_) => deserializer.deserialize_map(self),
+            Type::Enum(_) if self.options.index_discriminants => deserializer.deserialize_any(self),
             Type::Enum(_) => deserializer.deserialize_str(self),
-            Type::Option(opt) => match opt.ty() {
-                Type::Option(_) => deserializer.deserialize_any(self),
-                _ => deserializer.deserialize_option(self),
-            },
+            Type::Option(opt) => {
+                let nested = match self.plan_kind() {
+                    Some(PlanKind::Option(node)) => matches!(node.ty, Type::Option(_)),
+                    _ => matches!(opt.ty(), Type::Option(_)),
+                };
+                if !nested {
+                    deserializer.deserialize_option(self)
+                } else if self.options.lenient_nested_options {
+                    deserializer.deserialize_any(LenientNestedOption(self))
+                } else {
+                    deserializer.deserialize_any(self)
+                }
+            }
             Type::Result(_) => deserializer.deserialize_map(self),
-            Type::Flags(_) => deserializer.deserialize_seq(self),
+            Type::Flags(_) => match self.options.flags {
+                FlagsRepr::Names => deserializer.deserialize_seq(self),
+                FlagsRepr::Object => deserializer.deserialize_map(self),
+                FlagsRepr::Bitmask => deserializer.deserialize_u64(self),
+            },
             _ => deserializer.deserialize_any(self),
         }
     }
 }
 
-impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
+impl<'a, 'de> Visitor<'de> for DeserializeValWith<'a> {
     type Value = Val;
 
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
         // TODO: better messages
-        write!(formatter, "a {:?}", self.0)
+        write!(formatter, "a {:?}", self.ty)
     }
 
     fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        match self.0 {
+        match self.ty {
             Type::Bool => Ok(Val::Bool(v)),
             _ => Err(de::Error::invalid_type(de::Unexpected::Bool(v), &self)),
         }
@@ -59,7 +428,15 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         E: de::Error,
     {
-        match self.0 {
+        if let (Type::Flags(_) | Type::Char, Ok(v)) = (self.ty, u64::try_from(v)) {
+            return self.visit_u64(v);
+        }
+        if let (Type::Enum(_), true, Ok(v)) =
+            (self.ty, self.options.index_discriminants, u64::try_from(v))
+        {
+            return self.visit_u64(v);
+        }
+        match self.ty {
             Type::U8 => u8::try_from(v).map(Val::U8),
             Type::S8 => i8::try_from(v).map(Val::S8),
             Type::U16 => u16::try_from(v).map(Val::U16),
@@ -68,6 +445,9 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
             Type::S32 => i32::try_from(v).map(Val::S32),
             Type::U64 => u64::try_from(v).map(Val::U64),
             Type::S64 => Ok(Val::S64(v)),
+            Type::Float32 | Type::Float64 => {
+                return self.int_to_float(v.into(), Unexpected::Signed(v))
+            }
             _ => return Err(de::Error::invalid_type(de::Unexpected::Signed(v), &self)),
         }
         .map_err(|_| de::Error::invalid_value(de::Unexpected::Signed(v), &self))
@@ -77,7 +457,34 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         E: de::Error,
     {
-        match self.0 {
+        if let (Type::Enum(enum_), true) = (self.ty, self.options.index_discriminants) {
+            let name = usize::try_from(v)
+                .ok()
+                .and_then(|idx| enum_.names().nth(idx));
+            return match name {
+                Some(name) => enum_.new_val(name).map_err(de::Error::custom),
+                None => Err(de::Error::invalid_value(Unexpected::Unsigned(v), &self)),
+            };
+        }
+        if let (Type::Char, CharRepr::CodePoint) = (self.ty, self.options.chars) {
+            return u32::try_from(v)
+                .ok()
+                .and_then(char::from_u32)
+                .map(Val::Char)
+                .ok_or_else(|| de::Error::invalid_value(Unexpected::Unsigned(v), &self));
+        }
+        if let Type::Flags(flags) = self.ty {
+            let names = flags
+                .names()
+                .enumerate()
+                .filter_map(|(idx, name)| (idx < 64 && v & (1 << idx) != 0).then_some(name))
+                .collect::<Vec<_>>();
+            if v.count_ones() as usize != names.len() {
+                return Err(de::Error::invalid_value(Unexpected::Unsigned(v), &self));
+            }
+            return flags.new_val(&names).map_err(de::Error::custom);
+        }
+        match self.ty {
             Type::U8 => u8::try_from(v).map(Val::U8),
             Type::S8 => i8::try_from(v).map(Val::S8),
             Type::U16 => u16::try_from(v).map(Val::U16),
@@ -86,6 +493,9 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
             Type::S32 => i32::try_from(v).map(Val::S32),
             Type::U64 => Ok(Val::U64(v)),
             Type::S64 => i64::try_from(v).map(Val::S64),
+            Type::Float32 | Type::Float64 => {
+                return self.int_to_float(v.into(), Unexpected::Unsigned(v))
+            }
             _ => return Err(de::Error::invalid_type(de::Unexpected::Unsigned(v), &self)),
         }
         .map_err(|_| de::Error::invalid_value(de::Unexpected::Unsigned(v), &self))
@@ -95,12 +505,18 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         E: de::Error,
     {
-        // TODO: should this try to deserialize ints?
-        match self.0 {
-            // TODO: Should this do some precision check?
-            Type::Float32 => Ok(Val::Float32(v as f32)),
+        match self.ty {
+            Type::Float32 => self.f64_to_float32(v),
             Type::Float64 => Ok(Val::Float64(v)),
-            _ => return Err(de::Error::invalid_type(de::Unexpected::Float(v), &self)),
+            Type::U8
+            | Type::S8
+            | Type::U16
+            | Type::S16
+            | Type::U32
+            | Type::S32
+            | Type::U64
+            | Type::S64 => self.float_to_int(v),
+            _ => Err(de::Error::invalid_type(de::Unexpected::Float(v), &self)),
         }
     }
 
@@ -108,7 +524,7 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         E: de::Error,
     {
-        match self.0 {
+        match self.ty {
             Type::Char => Ok(Val::Char(v)),
             _ => Err(de::Error::invalid_type(de::Unexpected::Char(v), &self)),
         }
@@ -118,17 +534,25 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         E: de::Error,
     {
-        match self.0 {
+        match self.ty {
             Type::U8 | Type::U16 | Type::U32 | Type::U64 => {
-                let v = v
-                    .parse()
-                    .map_err(|_| de::Error::invalid_value(Unexpected::Str(v), &self))?;
+                let parsed = if self.options.extended_int_syntax {
+                    parse_extended_int(v).and_then(|v| u64::try_from(v).ok())
+                } else {
+                    v.parse().ok()
+                };
+                let v =
+                    parsed.ok_or_else(|| de::Error::invalid_value(Unexpected::Str(v), &self))?;
                 self.visit_u64(v)
             }
             Type::S8 | Type::S16 | Type::S32 | Type::S64 => {
-                let v = v
-                    .parse()
-                    .map_err(|_| de::Error::invalid_value(Unexpected::Str(v), &self))?;
+                let parsed = if self.options.extended_int_syntax {
+                    parse_extended_int(v).and_then(|v| i64::try_from(v).ok())
+                } else {
+                    v.parse().ok()
+                };
+                let v =
+                    parsed.ok_or_else(|| de::Error::invalid_value(Unexpected::Str(v), &self))?;
                 self.visit_i64(v)
             }
             Type::Float32 => {
@@ -136,7 +560,10 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
                     "NaN" => f32::NAN,
                     "Infinity" => f32::INFINITY,
                     "-Infinity" => f32::NEG_INFINITY,
-                    _ => return Err(de::Error::invalid_value(de::Unexpected::Str(v), &self)),
+                    _ => match nan_bits(v).and_then(|bits| u32::try_from(bits).ok()) {
+                        Some(bits) if f32::from_bits(bits).is_nan() => f32::from_bits(bits),
+                        _ => return Err(de::Error::invalid_value(de::Unexpected::Str(v), &self)),
+                    },
                 };
                 Ok(Val::Float32(val))
             }
@@ -145,15 +572,31 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
                     "NaN" => f64::NAN,
                     "Infinity" => f64::INFINITY,
                     "-Infinity" => f64::NEG_INFINITY,
-                    _ => return Err(de::Error::invalid_value(de::Unexpected::Str(v), &self)),
+                    _ => match nan_bits(v) {
+                        Some(bits) if f64::from_bits(bits).is_nan() => f64::from_bits(bits),
+                        _ => return Err(de::Error::invalid_value(de::Unexpected::Str(v), &self)),
+                    },
                 };
                 Ok(Val::Float64(val))
             }
-            Type::String => Ok(Val::String(v.into())),
+            Type::String => {
+                self.check_string_len(v.len())?;
+                Ok(Val::String(v.into()))
+            }
             Type::Char if v.chars().take(2).count() == 1 => {
                 Ok(Val::Char(v.chars().next().unwrap()))
             }
-            Type::Enum(enum_) => enum_.new_val(v).map_err(de::Error::custom),
+            Type::Enum(enum_) => {
+                let v = self.resolve_case(v, enum_.names())?;
+                if !enum_.names().any(|n| n == v) {
+                    return Err(de::Error::custom(unknown_name(
+                        "enum case",
+                        v,
+                        enum_.names(),
+                    )));
+                }
+                enum_.new_val(v).map_err(de::Error::custom)
+            }
             _ => Err(de::Error::invalid_type(de::Unexpected::Str(v), &self)),
         }
     }
@@ -162,8 +605,11 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         E: de::Error,
     {
-        match self.0 {
-            Type::String => Ok(Val::String(v.into_boxed_str())),
+        match self.ty {
+            Type::String => {
+                self.check_string_len(v.len())?;
+                Ok(Val::String(v.into_boxed_str()))
+            }
             _ => self.visit_str(&v),
         }
     }
@@ -172,8 +618,11 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         E: de::Error,
     {
-        match &self.0 {
+        match self.ty {
             Type::List(list) if matches!(list.ty(), Type::U8) => {
+                self.check_list_len(v.len())?;
+                self.count_values(v.len())?;
+                self.count_bytes(v.len())?;
                 let bytes = v.iter().map(|b| Val::U8(*b)).collect();
                 list.new_val(bytes).map_err(de::Error::custom)
             }
@@ -192,7 +641,7 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         E: de::Error,
     {
-        match &self.0 {
+        match self.ty {
             Type::Option(opt) => opt.new_val(None).map_err(de::Error::custom),
             _ => Err(de::Error::invalid_type(de::Unexpected::Option, &self)),
         }
@@ -202,12 +651,13 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         D: Deserializer<'de>,
     {
-        match &self.0 {
+        match self.ty {
             Type::Option(opt) => {
-                let v = if let Type::Option(_) = opt.ty() {
-                    deserializer.deserialize_map(DeserializeVal(&opt.ty()))?
-                } else {
-                    deserializer.deserialize_any(DeserializeVal(&opt.ty()))?
+                let v = match self.plan_kind() {
+                    Some(PlanKind::Option(node)) => {
+                        deserialize_some(deserializer, self.planned_child(node))?
+                    }
+                    _ => deserialize_some(deserializer, self.child(&opt.ty()))?,
                 };
                 opt.new_val(Some(v)).map_err(de::Error::custom)
             }
@@ -219,21 +669,44 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         A: de::SeqAccess<'de>,
     {
-        match &self.0 {
+        match self.ty {
             Type::List(list) => {
-                let ty = list.ty();
-                let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default());
-                while let Some(v) = seq.next_element_seed(DeserializeVal(&ty))? {
+                let ty;
+                let elem = match self.plan_kind() {
+                    Some(PlanKind::List(node)) => self.planned_child(node),
+                    _ => {
+                        ty = list.ty();
+                        self.child(&ty)
+                    }
+                };
+                // Arrays of `u8` count towards `max_bytes` like `visit_bytes`.
+                let bytes = matches!(elem.ty, Type::U8);
+                let mut values = Vec::with_capacity(self.prealloc_len(seq.size_hint()));
+                while let Some(v) = seq.next_element_seed(elem)? {
+                    self.check_list_len(values.len() + 1)?;
+                    if bytes {
+                        self.count_bytes(1)?;
+                    }
                     values.push(v);
                 }
                 list.new_val(values.into()).map_err(de::Error::custom)
             }
             Type::Tuple(tuple) => {
                 let len = tuple.types().len();
+                let tys: Vec<Type>;
+                let elems: Vec<DeserializeValWith> = match self.plan_kind() {
+                    Some(PlanKind::Tuple(nodes)) => {
+                        nodes.iter().map(|node| self.planned_child(node)).collect()
+                    }
+                    _ => {
+                        tys = tuple.types().collect();
+                        tys.iter().map(|ty| self.child(ty)).collect()
+                    }
+                };
                 let mut values = Vec::with_capacity(len);
-                for ty in tuple.types() {
+                for elem in elems {
                     let v = seq
-                        .next_element_seed(DeserializeVal(&ty))?
+                        .next_element_seed(elem)?
                         .ok_or_else(|| de::Error::invalid_length(values.len(), &self))?;
                     values.push(v);
                 }
@@ -246,10 +719,20 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
                     .map_err(de::Error::custom)
             }
             Type::Flags(flags) => {
-                let mut names = Vec::with_capacity(seq.size_hint().unwrap_or_default());
-                while let Some(name) = seq.next_element()? {
+                let len = seq.size_hint().unwrap_or_default();
+                let mut names = Vec::with_capacity(len.min(flags.names().len()));
+                // Owned, as readers can't lend out borrowed strings.
+                while let Some(name) = seq.next_element::<String>()? {
+                    if !flags.names().any(|n| n == name) {
+                        return Err(de::Error::custom(unknown_name(
+                            "flag",
+                            &name,
+                            flags.names(),
+                        )));
+                    }
                     names.push(name);
                 }
+                let names = names.iter().map(String::as_str).collect::<Vec<_>>();
                 flags.new_val(&names).map_err(de::Error::custom)
             }
             _ => Err(de::Error::invalid_type(de::Unexpected::Seq, &self)),
@@ -260,31 +743,54 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
     where
         A: de::MapAccess<'de>,
     {
-        match &self.0 {
+        match self.ty {
+            Type::List(list) if self.options.map_lists && is_map_list(list) => {
+                self.visit_map_list(list, map)
+            }
+
             Type::Record(rec) => {
-                let field_tys = rec
-                    .fields()
-                    .map(|f| (f.name, f.ty))
-                    .collect::<HashMap<_, _>>();
-                let mut field_vals = HashMap::with_capacity(rec.fields().len());
-                while let Some(name) = map.next_key::<Box<str>>()? {
-                    let ty = field_tys
-                        .get(&*name)
-                        .ok_or_else(|| de::Error::custom(format!("unknown field `{name}`")))?;
-                    let val = map.next_value_seed(DeserializeVal(ty))?;
-                    if field_vals.contains_key(&name) {
+                let planned = match self.plan_kind() {
+                    Some(PlanKind::Record { fields, index }) => Some((fields, index)),
+                    _ => None,
+                };
+                let field_tys: HashMap<_, _> = match planned {
+                    Some(_) => HashMap::new(),
+                    None => rec
+                        .fields()
+                        .enumerate()
+                        .map(|(idx, f)| (f.name, (idx, f.ty)))
+                        .collect(),
+                };
+                let mut field_vals = vec![None; rec.fields().len()];
+                while let Some(key) = map.next_key::<Box<str>>()? {
+                    let name = self.resolve_name(&key, rec.fields().map(|f| f.name))?;
+                    let unknown = || {
+                        de::Error::custom(unknown_name("field", name, rec.fields().map(|f| f.name)))
+                    };
+                    let (idx, val) = match planned {
+                        Some((fields, index)) => {
+                            let idx = *index.get(name).ok_or_else(unknown)?;
+                            (idx, map.next_value_seed(self.planned_child(&fields[idx]))?)
+                        }
+                        None => {
+                            let (idx, ty) = field_tys.get(name).ok_or_else(unknown)?;
+                            (*idx, map.next_value_seed(self.child(ty))?)
+                        }
+                    };
+                    if field_vals[idx].is_some() {
                         return Err(de::Error::custom(format!("duplicate field `{name}`")));
                     }
-                    field_vals.insert(name, val);
+                    field_vals[idx] = Some(val);
                 }
                 let values = rec
                     .fields()
-                    .map(|field| {
-                        if let Some(v) = field_vals.remove(field.name) {
-                            Ok(v)
-                        } else if let Type::Option(opt) = field.ty {
+                    .zip(field_vals)
+                    .map(|(field, v)| match (v, field.ty) {
+                        (Some(v), _) => Ok(v),
+                        (None, Type::Option(opt)) if !self.options.require_all_fields => {
                             opt.new_val(None).map_err(de::Error::custom)
-                        } else {
+                        }
+                        (None, _) => {
                             Err(de::Error::custom(format!("missing field `{}`", field.name)))
                         }
                     })
@@ -293,30 +799,80 @@ impl<'a, 'de> Visitor<'de> for DeserializeVal<'a> {
                 rec.new_val(values).map_err(de::Error::custom)
             }
 
+            Type::Flags(flags) => {
+                let mut seen = Vec::with_capacity(flags.names().len());
+                let mut names = Vec::new();
+                while let Some((name, set)) = map.next_entry::<String, bool>()? {
+                    if !flags.names().any(|n| n == name) {
+                        return Err(de::Error::custom(unknown_name(
+                            "flag",
+                            &name,
+                            flags.names(),
+                        )));
+                    }
+                    if seen.contains(&name) {
+                        return Err(de::Error::custom(format!("duplicate flag `{name}`")));
+                    }
+                    if set {
+                        names.push(name.clone());
+                    }
+                    seen.push(name);
+                }
+                let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+                flags.new_val(&names).map_err(de::Error::custom)
+            }
+
             Type::Variant(var) => single_entry_map(map, |map, name| {
-                let ty = var
-                    .cases()
-                    .find_map(|case| (case.name == name).then_some(case.ty))
-                    .ok_or_else(|| de::Error::custom(format!("unknown variant `{name}`")))?;
-                let v = next_value_maybe(map, ty)?;
+                let name = self.resolve_case(name, var.cases().map(|case| case.name))?;
+                let unknown = || {
+                    de::Error::custom(unknown_name("variant", name, var.cases().map(|c| c.name)))
+                };
+                let v = match self.plan_kind() {
+                    Some(PlanKind::Variant { cases, index }) => {
+                        let idx = *index.get(name).ok_or_else(unknown)?;
+                        let seed = cases[idx].as_ref().map(|node| self.planned_child(node));
+                        next_value_maybe(map, seed)?
+                    }
+                    _ => {
+                        let ty = var
+                            .cases()
+                            .find_map(|case| (case.name == name).then_some(case.ty))
+                            .ok_or_else(unknown)?;
+                        next_value_maybe(map, self.child_maybe(ty.as_ref()))?
+                    }
+                };
                 var.new_val(name, v).map_err(de::Error::custom)
             }),
 
             Type::Option(opt) => single_entry_map(map, |map, name| {
                 if name != "value" {
-                    return Err(de::Error::unknown_field("name", &["value"]));
+                    return Err(de::Error::unknown_field(name, &["value"]));
                 }
-                let v = map.next_value_seed(DeserializeVal(&opt.ty()))?;
+                let v = match self.plan_kind() {
+                    Some(PlanKind::Option(node)) => {
+                        map.next_value_seed(self.planned_child(node))?
+                    }
+                    _ => map.next_value_seed(self.child(&opt.ty()))?,
+                };
                 opt.new_val(Some(v)).map_err(de::Error::custom)
             }),
 
             Type::Result(res) => single_entry_map(map, |map, name| {
-                let (ty, is_ok) = match name {
-                    "result" => (res.ok(), true),
-                    "error" => (res.err(), false),
+                let is_ok = match name {
+                    "result" => true,
+                    "error" => false,
                     _ => return Err(de::Error::unknown_variant(name, &["result", "error"])),
                 };
-                let v = next_value_maybe(map, ty)?;
+                let v = match self.plan_kind() {
+                    Some(PlanKind::Result { ok, err }) => {
+                        let node = if is_ok { ok } else { err };
+                        next_value_maybe(map, node.as_deref().map(|n| self.planned_child(n)))?
+                    }
+                    _ => {
+                        let ty = if is_ok { res.ok() } else { res.err() };
+                        next_value_maybe(map, self.child_maybe(ty.as_ref()))?
+                    }
+                };
                 if is_ok {
                     res.new_val(Ok(v))
                 } else {
@@ -337,11 +893,11 @@ fn single_entry_map<'de, A>(
 where
     A: de::MapAccess<'de>,
 {
-    let name: &str = map
+    let name: String = map
         .next_key()?
         .ok_or_else(|| de::Error::invalid_length(0, &"exactly one field"))?;
-    let v = f(&mut map, name)?;
-    if map.next_key::<&str>()?.is_some() {
+    let v = f(&mut map, &name)?;
+    if map.next_key::<IgnoredAny>()?.is_some() {
         return Err(de::Error::invalid_length(
             map.size_hint().unwrap_or(2),
             &"exactly one field",
@@ -350,12 +906,28 @@ where
     Ok(v)
 }
 
-fn next_value_maybe<'de, A>(map: &mut A, ty: Option<Type>) -> Result<Option<Val>, A::Error>
+fn deserialize_some<'de, D>(deserializer: D, inner: DeserializeValWith) -> Result<Val, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // A nested `option` under `some` is always written as a map, e.g.
+    // `{"value": null}`.
+    if matches!(inner.ty, Type::Option(_)) && !inner.has_codec() {
+        inner.enter()?;
+        return deserializer.deserialize_map(inner);
+    }
+    inner.deserialize(deserializer)
+}
+
+fn next_value_maybe<'de, A>(
+    map: &mut A,
+    seed: Option<DeserializeValWith>,
+) -> Result<Option<Val>, A::Error>
 where
     A: de::MapAccess<'de>,
 {
-    Ok(match ty {
-        Some(t) => Some(map.next_value_seed(DeserializeVal(&t))?),
+    Ok(match seed {
+        Some(seed) => Some(map.next_value_seed(seed)?),
         None => {
             map.next_value::<()>()?;
             None
@@ -363,6 +935,160 @@ where
     })
 }
 
+/// A [`Visitor`] for `option<option<T>>` implementing
+/// [`Options::lenient_nested_options`].
+struct LenientNestedOption<'a>(DeserializeValWith<'a>);
+
+impl<'a> LenientNestedOption<'a> {
+    /// Deserializes the innermost non-`option` payload from `deserializer`
+    /// and wraps it in `some` for each level of `option`.
+    fn some_wrapped<'de, D: Deserializer<'de>>(self, deserializer: D) -> Result<Val, D::Error> {
+        wrap_some(&self.0, |d| d.deserialize(deserializer))
+    }
+}
+
+fn wrap_some<E: de::Error>(
+    outer: &DeserializeValWith,
+    f: impl FnOnce(DeserializeValWith) -> Result<Val, E>,
+) -> Result<Val, E> {
+    let Type::Option(opt) = outer.ty else {
+        return f(*outer);
+    };
+    let payload = opt.ty();
+    let inner = outer.child(&payload);
+    let v = if matches!(payload, Type::Option(_)) && !inner.has_codec() {
+        inner.enter()?;
+        wrap_some(&inner, f)?
+    } else {
+        f(inner)?
+    };
+    opt.new_val(Some(v)).map_err(de::Error::custom)
+}
+
+impl<'a, 'de> Visitor<'de> for LenientNestedOption<'a> {
+    type Value = Val;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.expecting(formatter)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        self.some_wrapped(v.into_deserializer())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.some_wrapped(v.into_deserializer())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.some_wrapped(v.into_deserializer())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        self.some_wrapped(v.into_deserializer())
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> Result<Self::Value, E> {
+        self.some_wrapped(v.into_deserializer())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        self.some_wrapped(v.into_deserializer())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        self.some_wrapped(v.into_deserializer())
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        self.some_wrapped(BytesDeserializer::new(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.0.visit_none()
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.0.visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.0.visit_some(deserializer)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        self.some_wrapped(SeqAccessDeserializer::new(seq))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let Type::Option(opt) = self.0.ty else {
+            return Err(de::Error::invalid_type(Unexpected::Map, &self));
+        };
+        let key = map.next_key::<String>()?;
+        if key.as_deref() == Some("value") {
+            let v = map.next_value_seed(self.0.child(&opt.ty()))?;
+            if map.next_key::<IgnoredAny>()?.is_some() {
+                return Err(de::Error::invalid_length(
+                    map.size_hint().unwrap_or(2),
+                    &"exactly one field",
+                ));
+            }
+            return opt.new_val(Some(v)).map_err(de::Error::custom);
+        }
+        let map = MapAccessDeserializer::new(ReplayKey { key, map });
+        self.some_wrapped(map)
+    }
+}
+
+/// A [`de::MapAccess`] that yields an already-consumed key before continuing
+/// with the wrapped map.
+struct ReplayKey<A> {
+    key: Option<String>,
+    map: A,
+}
+
+impl<'de, A> de::MapAccess<'de> for ReplayKey<A>
+where
+    A: de::MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.key.take() {
+            Some(key) => {
+                let key: de::value::StringDeserializer<A::Error> = key.into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            None => self.map.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.map.next_value_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.map
+            .size_hint()
+            .map(|n| n + usize::from(self.key.is_some()))
+    }
+}
+
 #[cfg(all(test, feature = "json"))]
 mod tests {
     use serde_json::json;
@@ -383,6 +1109,90 @@ mod tests {
         assert_val_json(Val::String("☃☃☃".into()), json!("☃☃☃"));
     }
 
+    #[test]
+    fn string_length_limit() {
+        let options = Options::new().limits(crate::Limits {
+            max_string_len: 2,
+            ..Default::default()
+        });
+        let ty = Type::String;
+        let seed = || DeserializeValWith::new(&ty, &options);
+        assert!(seed().deserialize(json!("ab")).is_ok());
+        let err = seed().deserialize(json!("abc")).unwrap_err();
+        assert!(err.to_string().contains("string length"), "{err}");
+    }
+
+    #[test]
+    fn numeric_coercion() {
+        assert_val_json(Val::Float64(1.0), json!(1));
+        assert_val_json(
+            Val::Float64(-9007199254740992.0),
+            json!(-9007199254740992i64),
+        );
+        assert_val_json(Val::Float32(16777216.0), json!(16777216));
+        assert_val_json(Val::U8(2), json!(2.0));
+        assert_val_json(Val::S64(-2), json!(-2.0));
+
+        let de = |ty: &Type, json| DeserializeVal(ty).deserialize(json);
+        assert!(de(&Type::Float64, json!(9007199254740993u64)).is_err());
+        assert!(de(&Type::Float32, json!(16777217)).is_err());
+        assert!(de(&Type::U8, json!(2.5)).is_err());
+        assert!(de(&Type::U8, json!(256.0)).is_err());
+        assert!(de(&Type::S64, json!(1e19)).is_err());
+        assert!(de(&Type::U64, json!(1e19)).is_ok());
+
+        let options = Options::new().strict_float32(true);
+        let strict = |json| DeserializeValWith::new(&Type::Float32, &options).deserialize(json);
+        assert_eq!(strict(json!(1.1)).unwrap(), Val::Float32(1.1));
+        assert_eq!(strict(json!(1.1f32 as f64)).unwrap(), Val::Float32(1.1));
+        assert!(strict(json!(0.1234567891)).is_err());
+        assert!(strict(json!(1e39)).is_err());
+        assert!(de(&Type::Float32, json!(0.1234567891)).is_ok());
+    }
+
+    #[test]
+    fn nan_payloads() {
+        let de = |ty: &Type, json| DeserializeVal(ty).deserialize(json);
+        match de(&Type::Float32, json!("NaN:0x7fc00001")).unwrap() {
+            Val::Float32(v) => assert_eq!(v.to_bits(), 0x7fc00001),
+            val => panic!("unexpected {val:?}"),
+        }
+        match de(&Type::Float64, json!("NaN:0xfff8000000000001")).unwrap() {
+            Val::Float64(v) => assert_eq!(v.to_bits(), 0xfff8000000000001),
+            val => panic!("unexpected {val:?}"),
+        }
+        // Not NaNs
+        assert!(de(&Type::Float32, json!("NaN:0x3f800000")).is_err());
+        assert!(de(&Type::Float32, json!("NaN:0x7fc000001")).is_err());
+        assert!(de(&Type::Float64, json!("NaN:0x")).is_err());
+    }
+
+    #[test]
+    fn extended_int_syntax() {
+        let options = Options::new().extended_int_syntax(true);
+        let de = |ty: &Type, json| DeserializeValWith::new(ty, &options).deserialize(json);
+        assert_eq!(de(&Type::U8, json!("0xff")).unwrap(), Val::U8(255));
+        assert_eq!(de(&Type::U16, json!("0o755")).unwrap(), Val::U16(0o755));
+        assert_eq!(de(&Type::U8, json!("0b1010")).unwrap(), Val::U8(10));
+        assert_eq!(
+            de(&Type::U32, json!("1_000_000")).unwrap(),
+            Val::U32(1_000_000)
+        );
+        assert_eq!(de(&Type::S8, json!("-0x80")).unwrap(), Val::S8(-128));
+        assert_eq!(de(&Type::S64, json!("42")).unwrap(), Val::S64(42));
+        assert!(de(&Type::U8, json!("0x100")).is_err());
+        assert!(de(&Type::S8, json!("0x80")).is_err());
+        assert!(de(&Type::U8, json!("-0x1")).is_err());
+        assert!(de(&Type::U32, json!("1__000")).is_err());
+        assert!(de(&Type::U32, json!("_1")).is_err());
+        assert!(de(&Type::U32, json!("0x")).is_err());
+        assert!(de(&Type::U32, json!("0x-1")).is_err());
+
+        let de = |ty: &Type, json| DeserializeVal(ty).deserialize(json);
+        assert!(de(&Type::U8, json!("0xff")).is_err());
+        assert!(de(&Type::U32, json!("1_000")).is_err());
+    }
+
     fn assert_val_json(val: Val, json: serde_json::Value) {
         let ty = val.ty();
         let deserialized = DeserializeVal(&ty).deserialize(json).unwrap();
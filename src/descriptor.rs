@@ -0,0 +1,419 @@
+use serde::{Deserialize, Serialize};
+use wasmtime::component::Type;
+
+/// A serializable description of a component [`Type`].
+///
+/// Unlike [`Type`], a descriptor doesn't depend on the component it came
+/// from, so it can be persisted or sent alongside values. Primitive types are
+/// serialized as strings (e.g. `"u8"`) and compound types as single-entry
+/// objects (e.g. `{"list": "u8"}`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TypeDescriptor {
+    Bool,
+    S8,
+    U8,
+    S16,
+    U16,
+    S32,
+    U32,
+    S64,
+    U64,
+    Float32,
+    Float64,
+    Char,
+    String,
+    List(Box<TypeDescriptor>),
+    Record(Vec<FieldDescriptor>),
+    Tuple(Vec<TypeDescriptor>),
+    Variant(Vec<CaseDescriptor>),
+    Enum(Vec<String>),
+    Option(Box<TypeDescriptor>),
+    Result {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ok: Option<Box<TypeDescriptor>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        err: Option<Box<TypeDescriptor>>,
+    },
+    Flags(Vec<String>),
+    Own,
+    Borrow,
+}
+
+/// A `record` field in a [`TypeDescriptor`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldDescriptor {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: TypeDescriptor,
+}
+
+/// A `variant` case in a [`TypeDescriptor`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaseDescriptor {
+    pub name: String,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub ty: Option<TypeDescriptor>,
+}
+
+impl TypeDescriptor {
+    /// Returns true if this descriptor describes the given [`Type`].
+    ///
+    /// Types are compared lazily, stopping at the first difference.
+    pub fn describes(&self, ty: &Type) -> bool {
+        match (self, ty) {
+            (Self::Bool, Type::Bool)
+            | (Self::S8, Type::S8)
+            | (Self::U8, Type::U8)
+            | (Self::S16, Type::S16)
+            | (Self::U16, Type::U16)
+            | (Self::S32, Type::S32)
+            | (Self::U32, Type::U32)
+            | (Self::S64, Type::S64)
+            | (Self::U64, Type::U64)
+            | (Self::Float32, Type::Float32)
+            | (Self::Float64, Type::Float64)
+            | (Self::Char, Type::Char)
+            | (Self::String, Type::String)
+            | (Self::Own, Type::Own(_))
+            | (Self::Borrow, Type::Borrow(_)) => true,
+            (Self::List(desc), Type::List(list)) => desc.describes(&list.ty()),
+            (Self::Record(fields), Type::Record(rec)) => {
+                all_pairs(fields, rec.fields(), |desc, field| {
+                    desc.name == field.name && desc.ty.describes(&field.ty)
+                })
+            }
+            (Self::Tuple(types), Type::Tuple(tuple)) => {
+                all_pairs(types, tuple.types(), |desc, ty| desc.describes(&ty))
+            }
+            (Self::Variant(cases), Type::Variant(var)) => {
+                all_pairs(cases, var.cases(), |desc, case| {
+                    desc.name == case.name
+                        && match (&desc.ty, &case.ty) {
+                            (Some(desc), Some(ty)) => desc.describes(ty),
+                            (None, None) => true,
+                            _ => false,
+                        }
+                })
+            }
+            (Self::Enum(names), Type::Enum(enum_)) => {
+                all_pairs(names, enum_.names(), |desc, name| desc == name)
+            }
+            (Self::Option(desc), Type::Option(opt)) => desc.describes(&opt.ty()),
+            (Self::Result { ok, err }, Type::Result(res)) => {
+                payload_describes(ok.as_deref(), res.ok().as_ref())
+                    && payload_describes(err.as_deref(), res.err().as_ref())
+            }
+            (Self::Flags(names), Type::Flags(flags)) => {
+                all_pairs(names, flags.names(), |desc, name| desc == name)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn payload_describes(desc: Option<&TypeDescriptor>, ty: Option<&Type>) -> bool {
+    match (desc, ty) {
+        (Some(desc), Some(ty)) => desc.describes(ty),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Returns true if `a` and `b` have the same length and `f` holds for every
+/// pair of elements.
+fn all_pairs<A, B>(
+    a: impl IntoIterator<Item = A>,
+    b: impl IntoIterator<Item = B>,
+    mut f: impl FnMut(A, B) -> bool,
+) -> bool {
+    let (mut a, mut b) = (a.into_iter(), b.into_iter());
+    loop {
+        match (a.next(), b.next()) {
+            (Some(a), Some(b)) if f(a, b) => (),
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+impl From<&Type> for TypeDescriptor {
+    fn from(ty: &Type) -> Self {
+        match ty {
+            Type::Bool => Self::Bool,
+            Type::S8 => Self::S8,
+            Type::U8 => Self::U8,
+            Type::S16 => Self::S16,
+            Type::U16 => Self::U16,
+            Type::S32 => Self::S32,
+            Type::U32 => Self::U32,
+            Type::S64 => Self::S64,
+            Type::U64 => Self::U64,
+            Type::Float32 => Self::Float32,
+            Type::Float64 => Self::Float64,
+            Type::Char => Self::Char,
+            Type::String => Self::String,
+            Type::List(list) => Self::List(Box::new(Self::from(&list.ty()))),
+            Type::Record(rec) => Self::Record(
+                rec.fields()
+                    .map(|field| FieldDescriptor {
+                        name: field.name.into(),
+                        ty: Self::from(&field.ty),
+                    })
+                    .collect(),
+            ),
+            Type::Tuple(tuple) => Self::Tuple(tuple.types().map(|ty| Self::from(&ty)).collect()),
+            Type::Variant(var) => Self::Variant(
+                var.cases()
+                    .map(|case| CaseDescriptor {
+                        name: case.name.into(),
+                        ty: case.ty.as_ref().map(Self::from),
+                    })
+                    .collect(),
+            ),
+            Type::Enum(enum_) => Self::Enum(enum_.names().map(Into::into).collect()),
+            Type::Option(opt) => Self::Option(Box::new(Self::from(&opt.ty()))),
+            Type::Result(res) => Self::Result {
+                ok: res.ok().map(|ty| Box::new(Self::from(&ty))),
+                err: res.err().map(|ty| Box::new(Self::from(&ty))),
+            },
+            Type::Flags(flags) => Self::Flags(flags.names().map(Into::into).collect()),
+            Type::Own(_) => Self::Own,
+            Type::Borrow(_) => Self::Borrow,
+        }
+    }
+}
+
+impl From<Type> for TypeDescriptor {
+    fn from(ty: Type) -> Self {
+        Self::from(&ty)
+    }
+}
+
+#[cfg(feature = "build-types")]
+impl TypeDescriptor {
+    /// Builds a [`Type`] matching this descriptor, e.g. to decode values
+    /// without the component they came from.
+    ///
+    /// The type is taken from a small component compiled for the purpose,
+    /// so this is relatively expensive, and the result isn't equal to any
+    /// other component's types. `own` and `borrow` can't be built since
+    /// their descriptors don't identify the resource.
+    pub fn to_type(&self) -> wasmtime::Result<Type> {
+        use wasmtime::{
+            component::{Component, Linker},
+            Config, Engine, Store,
+        };
+
+        let mut defs = vec![];
+        let ty = wat_type(self, &mut defs)?;
+        let defs = defs.join("\n");
+        // Wrapping the type in a tuple ensures the lifted function always
+        // returns its results indirectly through a single `i32` pointer.
+        let wat = format!(
+            r#"
+            (component
+                (core module $m
+                    (memory (export "memory") 1)
+                    (func (export "f") (result i32) unreachable)
+                )
+                (core instance $i (instantiate $m))
+                {defs}
+                (func (export "f") (result (tuple {ty} u32))
+                    (canon lift (core func $i "f") (memory $i "memory"))
+                )
+            )
+            "#
+        );
+        let engine = Engine::new(Config::new().wasm_component_model(true))?;
+        let component = Component::new(&engine, wat)?;
+        let mut store = Store::new(&engine, ());
+        let instance = Linker::new(&engine).instantiate(&mut store, &component)?;
+        let func = instance
+            .get_func(&mut store, "f")
+            .ok_or_else(|| wasmtime::Error::msg("missing export func"))?;
+        match &func.results(&store)[0] {
+            Type::Tuple(tuple) => tuple
+                .types()
+                .next()
+                .ok_or_else(|| wasmtime::Error::msg("empty result tuple")),
+            ty => Err(wasmtime::Error::msg(format!(
+                "unexpected result type {ty:?}"
+            ))),
+        }
+    }
+}
+
+/// Returns the WAT for a type, pushing definitions of the named types it
+/// uses to `defs`. Named types must be exported to appear in a lifted
+/// function's signature, so they're referred to by their exports.
+#[cfg(feature = "build-types")]
+fn wat_type(ty: &TypeDescriptor, defs: &mut Vec<String>) -> wasmtime::Result<String> {
+    use std::fmt::Write;
+
+    use TypeDescriptor as D;
+
+    let def = match ty {
+        D::Bool => return Ok("bool".into()),
+        D::S8 => return Ok("s8".into()),
+        D::U8 => return Ok("u8".into()),
+        D::S16 => return Ok("s16".into()),
+        D::U16 => return Ok("u16".into()),
+        D::S32 => return Ok("s32".into()),
+        D::U32 => return Ok("u32".into()),
+        D::S64 => return Ok("s64".into()),
+        D::U64 => return Ok("u64".into()),
+        D::Float32 => return Ok("float32".into()),
+        D::Float64 => return Ok("float64".into()),
+        D::Char => return Ok("char".into()),
+        D::String => return Ok("string".into()),
+        D::List(ty) => return Ok(format!("(list {})", wat_type(ty, defs)?)),
+        D::Option(ty) => return Ok(format!("(option {})", wat_type(ty, defs)?)),
+        D::Tuple(types) => return Ok(format!("(tuple {})", wat_types(types, defs)?.join(" "))),
+        D::Result { ok, err } => {
+            let mut wat = "(result".to_string();
+            if let Some(ok) = ok {
+                write!(wat, " {}", wat_type(ok, defs)?)?;
+            }
+            if let Some(err) = err {
+                write!(wat, " (error {})", wat_type(err, defs)?)?;
+            }
+            return Ok(wat + ")");
+        }
+        D::Record(fields) => {
+            let types = wat_types(fields.iter().map(|field| &field.ty), defs)?;
+            let fields = fields
+                .iter()
+                .zip(types)
+                .map(|(field, ty)| format!("(field {} {ty})", wat_string(&field.name)))
+                .collect::<Vec<_>>();
+            format!("(record {})", fields.join(" "))
+        }
+        D::Variant(cases) => {
+            let mut wat = "(variant".to_string();
+            for case in cases {
+                write!(wat, " (case {}", wat_string(&case.name))?;
+                if let Some(ty) = &case.ty {
+                    write!(wat, " {}", wat_type(ty, defs)?)?;
+                }
+                wat.push(')');
+            }
+            wat + ")"
+        }
+        D::Enum(names) => format!("(enum {})", wat_strings(names)),
+        D::Flags(names) => format!("(flags {})", wat_strings(names)),
+        D::Own | D::Borrow => {
+            return Err(wasmtime::Error::msg(
+                "resource types can't be built from a descriptor",
+            ))
+        }
+    };
+    let n = defs.len();
+    defs.push(format!(
+        "(type $t{n} {def}) (export $e{n} \"t{n}\" (type $t{n}))"
+    ));
+    Ok(format!("$e{n}"))
+}
+
+#[cfg(feature = "build-types")]
+fn wat_types<'a>(
+    types: impl IntoIterator<Item = &'a TypeDescriptor>,
+    defs: &mut Vec<String>,
+) -> wasmtime::Result<Vec<String>> {
+    types.into_iter().map(|ty| wat_type(ty, defs)).collect()
+}
+
+/// Quotes a name as a WAT string, escaping anything but kebab-case
+/// characters so that invalid names are rejected by validation.
+#[cfg(feature = "build-types")]
+fn wat_string(name: &str) -> String {
+    let mut wat = String::from('"');
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '-' {
+            wat.push(c);
+        } else {
+            wat += &format!("\\u{{{:x}}}", c as u32);
+        }
+    }
+    wat + "\""
+}
+
+#[cfg(feature = "build-types")]
+fn wat_strings(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|name| wat_string(name))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use serde_json::json;
+    use wasmtime::component::Val;
+
+    use super::*;
+
+    #[test]
+    fn primitive_descriptors_json() {
+        assert_ty_json(&Val::Bool(true).ty(), json!("bool"));
+        assert_ty_json(&Val::U16(123).ty(), json!("u16"));
+        assert_ty_json(&Val::Float32(1.5).ty(), json!("float32"));
+        assert_ty_json(&Val::String("☃".into()).ty(), json!("string"));
+    }
+
+    #[test]
+    fn compound_descriptors_json() {
+        let desc = TypeDescriptor::Record(vec![
+            FieldDescriptor {
+                name: "required".into(),
+                ty: TypeDescriptor::U8,
+            },
+            FieldDescriptor {
+                name: "optional".into(),
+                ty: TypeDescriptor::Option(Box::new(TypeDescriptor::U8)),
+            },
+        ]);
+        assert_desc_json(
+            desc,
+            json!({"record": [
+                {"name": "required", "type": "u8"},
+                {"name": "optional", "type": {"option": "u8"}},
+            ]}),
+        );
+
+        let desc = TypeDescriptor::Variant(vec![
+            CaseDescriptor {
+                name: "without-payload".into(),
+                ty: None,
+            },
+            CaseDescriptor {
+                name: "with-payload".into(),
+                ty: Some(TypeDescriptor::U8),
+            },
+        ]);
+        assert_desc_json(
+            desc,
+            json!({"variant": [
+                {"name": "without-payload"},
+                {"name": "with-payload", "type": "u8"},
+            ]}),
+        );
+
+        let desc = TypeDescriptor::Result {
+            ok: Some(Box::new(TypeDescriptor::U8)),
+            err: None,
+        };
+        assert_desc_json(desc, json!({"result": {"ok": "u8"}}));
+    }
+
+    fn assert_ty_json(ty: &Type, json: serde_json::Value) {
+        assert_desc_json(TypeDescriptor::from(ty), json);
+    }
+
+    fn assert_desc_json(desc: TypeDescriptor, json: serde_json::Value) {
+        assert_eq!(serde_json::to_value(&desc).unwrap(), json);
+        let deserialized: TypeDescriptor = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized, desc);
+    }
+}
@@ -0,0 +1,97 @@
+use wasmtime::component::{Type, Val};
+
+/// Returns a deterministic example [`Val`] of the given [`Type`].
+///
+/// Numbers are zero, `char`s are `'x'` and `string`s are `"string"`. Compound
+/// values are filled in recursively: `list`s have one element, `option`s are
+/// `some`, `result`s are `ok`, `variant`s and `enum`s use their first case and
+/// `flags` have every flag set.
+///
+/// Resources (`own` and `borrow`) can't be created without a store, so they
+/// are avoided where the type allows, e.g. with an empty `list`, `none`, or
+/// another case. Returns `None` if the type requires a resource.
+pub fn example_val(ty: &Type) -> Option<Val> {
+    Some(match ty {
+        Type::Bool => Val::Bool(false),
+        Type::S8 => Val::S8(0),
+        Type::U8 => Val::U8(0),
+        Type::S16 => Val::S16(0),
+        Type::U16 => Val::U16(0),
+        Type::S32 => Val::S32(0),
+        Type::U32 => Val::U32(0),
+        Type::S64 => Val::S64(0),
+        Type::U64 => Val::U64(0),
+        Type::Float32 => Val::Float32(0.0),
+        Type::Float64 => Val::Float64(0.0),
+        Type::Char => Val::Char('x'),
+        Type::String => Val::String("string".into()),
+        Type::List(list) => {
+            let elements = example_val(&list.ty()).into_iter().collect();
+            list.new_val(elements).unwrap()
+        }
+        Type::Record(rec) => {
+            let fields = rec
+                .fields()
+                .map(|field| Some((field.name, example_val(&field.ty)?)))
+                .collect::<Option<Vec<_>>>()?;
+            rec.new_val(fields).unwrap()
+        }
+        Type::Tuple(tuple) => {
+            let values = tuple
+                .types()
+                .map(|ty| example_val(&ty))
+                .collect::<Option<_>>()?;
+            tuple.new_val(values).unwrap()
+        }
+        Type::Variant(var) => var.cases().find_map(|case| {
+            let payload = match &case.ty {
+                Some(ty) => Some(example_val(ty)?),
+                None => None,
+            };
+            Some(var.new_val(case.name, payload).unwrap())
+        })?,
+        Type::Enum(enum_) => enum_
+            .new_val(enum_.names().next().expect("enum has cases"))
+            .unwrap(),
+        Type::Option(opt) => opt.new_val(example_val(&opt.ty())).unwrap(),
+        Type::Result(res) => {
+            let payload = |ty: Option<Type>| match ty {
+                Some(ty) => example_val(&ty).map(Some),
+                None => Some(None),
+            };
+            let val = match payload(res.ok()) {
+                Some(ok) => Ok(ok),
+                None => Err(payload(res.err())?),
+            };
+            res.new_val(val).unwrap()
+        }
+        Type::Flags(flags) => flags.new_val(&flags.names().collect::<Vec<_>>()).unwrap(),
+        Type::Own(_) | Type::Borrow(_) => return None,
+    })
+}
+
+/// Returns the JSON form of [`example_val`].
+#[cfg(feature = "json")]
+pub fn example_json(ty: &Type) -> Option<serde_json::Value> {
+    let val = example_val(ty)?;
+    Some(serde_json::to_value(crate::SerializeVal(&val)).expect("example values serialize"))
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn primitive_examples() {
+        assert_eq!(example_json(&Val::Bool(true).ty()), Some(json!(false)));
+        assert_eq!(example_json(&Val::U16(1).ty()), Some(json!(0)));
+        assert_eq!(example_json(&Val::Float64(1.5).ty()), Some(json!(0.0)));
+        assert_eq!(example_json(&Val::Char('☃').ty()), Some(json!("x")));
+        assert_eq!(
+            example_json(&Val::String("".into()).ty()),
+            Some(json!("string"))
+        );
+    }
+}
@@ -1,18 +1,73 @@
 use serde::{de::DeserializeSeed, Deserializer, Serialize, Serializer};
 use wasmtime::component::{Type, Val};
 
+#[cfg(feature = "json")]
+mod canonical;
+#[cfg(feature = "json")]
+mod codec;
 mod de;
+mod descriptor;
+mod example;
+mod limits;
+mod options;
+mod plan;
 mod ser;
+#[cfg(feature = "proptest")]
+mod strategy;
+#[cfg(feature = "json")]
+mod stream;
+mod suggest;
+#[cfg(feature = "json")]
+mod typed;
+#[cfg(feature = "json")]
+mod validate;
+#[cfg(any(
+    feature = "wasi-clocks",
+    feature = "wasi-http",
+    feature = "wasi-filesystem"
+))]
+pub mod wasi;
 
-pub use de::DeserializeVal;
-pub use ser::SerializeVal;
+#[cfg(feature = "sha2")]
+pub use canonical::canonical_hash;
+#[cfg(feature = "json")]
+pub use canonical::{to_canonical_json, to_canonical_json_writer};
+#[cfg(feature = "json")]
+pub use codec::{Codec, CodecRegistry};
+pub use de::{DeserializeVal, DeserializeValWith};
+pub use descriptor::{CaseDescriptor, FieldDescriptor, TypeDescriptor};
+#[cfg(feature = "json")]
+pub use example::example_json;
+pub use example::example_val;
+pub use limits::{LimitExceeded, LimitKind, Limits};
+pub use options::{CharRepr, DuplicateKeys, FlagsRepr, Options};
+pub use plan::TypePlan;
+pub use ser::{SerializeVal, SerializeValWith};
+#[cfg(feature = "proptest")]
+pub use strategy::arb_val;
+#[cfg(feature = "json")]
+pub use stream::JsonListReader;
+#[cfg(feature = "json")]
+pub use typed::TypedVal;
+#[cfg(feature = "json")]
+pub use validate::{validate_json, validate_json_with, ValidationError};
 
 /// Deserialize a [`Val`] of the given [`Type`] from a [`Deserializer`].
 pub fn deserialize_val<'de, D: Deserializer<'de>>(
     deserializer: D,
     ty: &Type,
 ) -> Result<Val, D::Error> {
-    de::DeserializeVal(ty).deserialize(deserializer)
+    DeserializeVal(ty).deserialize(deserializer)
+}
+
+/// Deserialize a [`Val`] of the given [`Type`] from a [`Deserializer`] with
+/// the given [`Options`].
+pub fn deserialize_val_with<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    ty: &Type,
+    options: &Options,
+) -> Result<Val, D::Error> {
+    DeserializeValWith::new(ty, options).deserialize(deserializer)
 }
 
 /// Serialize a [`Val`] with a [`Serializer`].
@@ -20,6 +75,15 @@ pub fn serialize_val<S: Serializer>(serializer: S, val: &Val) -> Result<S::Ok, S
     SerializeVal(val).serialize(serializer)
 }
 
+/// Serialize a [`Val`] with a [`Serializer`] and the given [`Options`].
+pub fn serialize_val_with<S: Serializer>(
+    serializer: S,
+    val: &Val,
+    options: &Options,
+) -> Result<S::Ok, S::Error> {
+    SerializeValWith::new(val, options).serialize(serializer)
+}
+
 /// Deserialize a [`Val`] of the given [`Type`] from JSON.
 #[cfg(feature = "json")]
 pub fn from_json(ty: &Type, json: impl AsRef<[u8]>) -> serde_json::Result<Val> {
@@ -27,8 +91,124 @@ pub fn from_json(ty: &Type, json: impl AsRef<[u8]>) -> serde_json::Result<Val> {
     deserialize_val(&mut d, ty)
 }
 
+/// Deserialize a [`Val`] of the given [`Type`] from JSON with the given
+/// [`Options`].
+#[cfg(feature = "json")]
+pub fn from_json_with(
+    ty: &Type,
+    json: impl AsRef<[u8]>,
+    options: &Options,
+) -> serde_json::Result<Val> {
+    let mut d = serde_json::Deserializer::from_slice(json.as_ref());
+    deserialize_val_with(&mut d, ty, options)
+}
+
+/// Deserialize a [`Val`] of the given [`Type`] from a JSON reader.
+///
+/// The whole value is held in memory; see [`JsonListReader`] to process a
+/// large `list` element by element.
+#[cfg(feature = "json")]
+pub fn from_json_reader(ty: &Type, reader: impl std::io::Read) -> serde_json::Result<Val> {
+    from_json_reader_with(ty, reader, options::default_options())
+}
+
+/// Deserialize a [`Val`] of the given [`Type`] from a JSON reader with the
+/// given [`Options`].
+#[cfg(feature = "json")]
+pub fn from_json_reader_with(
+    ty: &Type,
+    reader: impl std::io::Read,
+    options: &Options,
+) -> serde_json::Result<Val> {
+    let mut d = serde_json::Deserializer::from_reader(reader);
+    let val = deserialize_val_with(&mut d, ty, options)?;
+    d.end()?;
+    Ok(val)
+}
+
 /// Serialize a [`Val`] to JSON.
 #[cfg(feature = "json")]
 pub fn to_json(val: &Val) -> serde_json::Result<String> {
     serde_json::to_string(&SerializeVal(val))
 }
+
+/// Serialize a [`Val`] to JSON with the given [`Options`].
+#[cfg(feature = "json")]
+pub fn to_json_with(val: &Val, options: &Options) -> serde_json::Result<String> {
+    serde_json::to_string(&SerializeValWith::new(val, options))
+}
+
+/// Serialize a [`Val`] as JSON into the given writer, e.g. a file or socket.
+#[cfg(feature = "json")]
+pub fn to_json_writer(writer: impl std::io::Write, val: &Val) -> serde_json::Result<()> {
+    to_json_writer_with(writer, val, options::default_options())
+}
+
+/// Serialize a [`Val`] as JSON into the given writer with the given
+/// [`Options`].
+#[cfg(feature = "json")]
+pub fn to_json_writer_with(
+    writer: impl std::io::Write,
+    val: &Val,
+    options: &Options,
+) -> serde_json::Result<()> {
+    serde_json::to_writer(writer, &SerializeValWith::new(val, options))
+}
+
+/// Serialize a [`Val`] to a JSON byte vector.
+#[cfg(feature = "json")]
+pub fn to_json_vec(val: &Val) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&SerializeVal(val))
+}
+
+/// Serialize a [`Val`] to a JSON byte vector with the given [`Options`].
+#[cfg(feature = "json")]
+pub fn to_json_vec_with(val: &Val, options: &Options) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&SerializeValWith::new(val, options))
+}
+
+/// Serialize a [`Val`] to pretty-printed JSON, indenting with `indent`
+/// (e.g. `"  "` or `"\t"`).
+#[cfg(feature = "json")]
+pub fn to_json_pretty(val: &Val, indent: &str) -> serde_json::Result<String> {
+    to_json_pretty_with(val, indent, options::default_options())
+}
+
+/// Serialize a [`Val`] to pretty-printed JSON, indenting with `indent`, with
+/// the given [`Options`].
+#[cfg(feature = "json")]
+pub fn to_json_pretty_with(
+    val: &Val,
+    indent: &str,
+    options: &Options,
+) -> serde_json::Result<String> {
+    let mut json = Vec::new();
+    to_json_pretty_writer_with(&mut json, val, indent, options)?;
+    // The serializer only writes valid UTF-8.
+    Ok(String::from_utf8(json).unwrap())
+}
+
+/// Serialize a [`Val`] as pretty-printed JSON, indenting with `indent`, into
+/// the given writer.
+#[cfg(feature = "json")]
+pub fn to_json_pretty_writer(
+    writer: impl std::io::Write,
+    val: &Val,
+    indent: &str,
+) -> serde_json::Result<()> {
+    to_json_pretty_writer_with(writer, val, indent, options::default_options())
+}
+
+/// Serialize a [`Val`] as pretty-printed JSON, indenting with `indent`, into
+/// the given writer with the given [`Options`].
+#[cfg(feature = "json")]
+pub fn to_json_pretty_writer_with(
+    writer: impl std::io::Write,
+    val: &Val,
+    indent: &str,
+    options: &Options,
+) -> serde_json::Result<()> {
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(writer, formatter);
+    SerializeValWith::new(val, options).serialize(&mut serializer)
+}
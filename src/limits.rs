@@ -0,0 +1,139 @@
+use std::fmt;
+
+/// Resource limits enforced while deserializing.
+///
+/// The defaults are unlimited; use struct update syntax to set specific
+/// limits, e.g. `Limits { max_depth: 64, ..Limits::default() }`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum nesting depth of values, where a top-level value has a
+    /// depth of 1.
+    pub max_depth: usize,
+    /// The maximum number of elements in a single `list`.
+    pub max_list_len: usize,
+    /// The maximum length in bytes of a single `string`.
+    pub max_string_len: usize,
+    /// The maximum total number of values, including nested values.
+    pub max_values: usize,
+    /// The maximum total number of bytes of `string` and `list<u8>` data.
+    pub max_bytes: usize,
+    /// The maximum length in bytes of the raw JSON of a single element read
+    /// by [`JsonListReader`](crate::JsonListReader), which buffers each
+    /// element before deserializing it.
+    pub max_element_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            max_list_len: usize::MAX,
+            max_string_len: usize::MAX,
+            max_values: usize::MAX,
+            max_bytes: usize::MAX,
+            max_element_len: usize::MAX,
+        }
+    }
+}
+
+/// The kind of limit reported by a [`LimitExceeded`] error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitKind {
+    Depth,
+    ListLength,
+    StringLength,
+    Values,
+    Bytes,
+    ElementLength,
+}
+
+/// An error indicating that one of the configured [`Limits`] was exceeded.
+///
+/// Deserialization errors are reported through the deserializer's own error
+/// type, so this is surfaced via its [`fmt::Display`] message; use
+/// [`LimitExceeded::from_error`] to recover it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitExceeded {
+    pub kind: LimitKind,
+    pub limit: usize,
+}
+
+impl LimitExceeded {
+    /// Recovers a `LimitExceeded` from a deserialization error (e.g. a
+    /// [`serde_json::Error`]) caused by one, or returns `None` for any other
+    /// error.
+    ///
+    /// The whole message must match, apart from a trailing
+    /// `" at line N column M"` position, so input echoed in other errors
+    /// (e.g. an unknown case name) can't be mistaken for a limit.
+    pub fn from_error(err: &impl fmt::Display) -> Option<Self> {
+        let message = err.to_string();
+        let message = strip_position(&message);
+        let rest = message
+            .strip_prefix("maximum ")?
+            .strip_suffix(" exceeded")?;
+        let (what, limit) = rest.rsplit_once(" of ")?;
+        let kind = LimitKind::ALL
+            .into_iter()
+            .find(|kind| kind.description() == what)?;
+        let exceeded = Self {
+            kind,
+            limit: limit.parse().ok()?,
+        };
+        (exceeded.to_string() == message).then_some(exceeded)
+    }
+}
+
+/// Strips the position serde_json appends to error messages.
+fn strip_position(message: &str) -> &str {
+    let Some((head, position)) = message.rsplit_once(" at line ") else {
+        return message;
+    };
+    let is_position = position
+        .split_once(" column ")
+        .is_some_and(|(line, column)| {
+            [line, column]
+                .iter()
+                .all(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        });
+    if is_position {
+        head
+    } else {
+        message
+    }
+}
+
+impl LimitKind {
+    const ALL: [Self; 6] = [
+        Self::Depth,
+        Self::ListLength,
+        Self::StringLength,
+        Self::Values,
+        Self::Bytes,
+        Self::ElementLength,
+    ];
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::Depth => "nesting depth",
+            Self::ListLength => "list length",
+            Self::StringLength => "string length",
+            Self::Values => "total number of values",
+            Self::Bytes => "total number of bytes",
+            Self::ElementLength => "element length",
+        }
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "maximum {} of {} exceeded",
+            self.kind.description(),
+            self.limit
+        )
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
@@ -0,0 +1,295 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use wasmtime::component::{types, Type};
+
+#[cfg(feature = "json")]
+use crate::CodecRegistry;
+use crate::Limits;
+
+/// Options controlling how [`Val`](wasmtime::component::Val)s are
+/// (de)serialized.
+///
+/// The defaults match the mapping documented in the README.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub(crate) lenient_nested_options: bool,
+    pub(crate) flags: FlagsRepr,
+    pub(crate) chars: CharRepr,
+    pub(crate) limits: Limits,
+    pub(crate) explicit_none_fields: bool,
+    pub(crate) require_all_fields: bool,
+    pub(crate) strict_float32: bool,
+    pub(crate) nan_payloads: bool,
+    pub(crate) extended_int_syntax: bool,
+    pub(crate) map_lists: bool,
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) case_insensitive: bool,
+    pub(crate) index_discriminants: bool,
+    pub(crate) aliases: HashMap<String, Vec<String>>,
+    #[cfg(feature = "json")]
+    pub(crate) codecs: Option<std::sync::Arc<CodecRegistry>>,
+    /// Set by [`to_canonical_json`](crate::to_canonical_json) to sort object
+    /// keys.
+    pub(crate) canonical: bool,
+}
+
+/// The representation of `flags` values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FlagsRepr {
+    /// An array of the names of set flags, e.g. `["read"]`.
+    #[default]
+    Names,
+    /// An object with an entry for every flag, e.g.
+    /// `{"read": true, "write": false}`.
+    Object,
+    /// An integer with bit `n` set if the `n`th declared flag is set, e.g.
+    /// `1`. Limited to 64 flags.
+    Bitmask,
+}
+
+/// The representation of `char` values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CharRepr {
+    /// A single-character string, e.g. `"a"`.
+    #[default]
+    String,
+    /// The Unicode scalar value as an integer, e.g. `97`.
+    CodePoint,
+}
+
+/// How duplicate keys are handled in map-like `list`s (see
+/// [`Options::map_lists`]), both when deserializing from an object and when
+/// serializing to one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Reject the input.
+    #[default]
+    Error,
+    /// Keep the first entry with a given key.
+    First,
+    /// Keep the last value for a given key, at the position of the first
+    /// entry with that key.
+    Last,
+}
+
+impl Options {
+    /// Returns the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// When deserializing `option<option<T>>`, additionally accept a plain
+    /// `<T>` as `some(some(T))`.
+    ///
+    /// Inputs are interpreted with the following precedence:
+    /// 1. `null` is `none`
+    /// 2. an object with a single `"value"` key is `some(<value>)`, so
+    ///    `{"value": null}` is `some(none)`
+    /// 3. anything else is `some(some(<T>))`
+    ///
+    /// This relies on the format being self-describing (e.g. JSON).
+    pub fn lenient_nested_options(mut self, lenient: bool) -> Self {
+        self.lenient_nested_options = lenient;
+        self
+    }
+
+    /// Sets the representation of `flags` values.
+    ///
+    /// When deserializing [`FlagsRepr::Object`], missing flags are unset.
+    pub fn flags(mut self, repr: FlagsRepr) -> Self {
+        self.flags = repr;
+        self
+    }
+
+    /// Sets the representation of `char` values.
+    ///
+    /// When deserializing [`CharRepr::CodePoint`], surrogates and integers
+    /// above `0x10FFFF` are rejected.
+    pub fn chars(mut self, repr: CharRepr) -> Self {
+        self.chars = repr;
+        self
+    }
+
+    /// When serializing, emit `none` record fields as `"field": null` rather
+    /// than omitting them.
+    pub fn explicit_none_fields(mut self, explicit: bool) -> Self {
+        self.explicit_none_fields = explicit;
+        self
+    }
+
+    /// When deserializing, require every record field to be present,
+    /// including `option` fields, which otherwise default to `none`.
+    pub fn require_all_fields(mut self, require: bool) -> Self {
+        self.require_all_fields = require;
+        self
+    }
+
+    /// When deserializing a `float32`, reject numbers that would lose
+    /// precision, e.g. `0.1234567891` or `1e39`.
+    ///
+    /// Numbers that are exactly representable as an f32 or are the shortest
+    /// decimal form of one (as serialized, e.g. `1.1`) are always accepted.
+    /// Integers are always converted exactly or rejected.
+    pub fn strict_float32(mut self, strict: bool) -> Self {
+        self.strict_float32 = strict;
+        self
+    }
+
+    /// When serializing, encode NaNs other than the canonical quiet NaN with
+    /// their bit pattern, e.g. `"NaN:0x7fc00001"`, rather than as `"NaN"`.
+    ///
+    /// This form is always accepted when deserializing.
+    pub fn nan_payloads(mut self, preserve: bool) -> Self {
+        self.nan_payloads = preserve;
+        self
+    }
+
+    /// When deserializing integers from strings, additionally accept `0x`,
+    /// `0o` and `0b` prefixes and `_` digit separators, e.g. `"0xff"`,
+    /// `"-0o755"` or `"1_000_000"`.
+    pub fn extended_int_syntax(mut self, extended: bool) -> Self {
+        self.extended_int_syntax = extended;
+        self
+    }
+
+    /// (De)serialize `list<tuple<string, T>>` as a JSON object, e.g.
+    /// `{"a": 1, "b": 2}` rather than `[["a", 1], ["b", 2]]`.
+    ///
+    /// Entries are serialized in list order, with duplicate keys handled per
+    /// [`Options::duplicate_keys`].
+    /// When deserializing, arrays of pairs are accepted as well, which relies
+    /// on the format being self-describing (e.g. JSON).
+    pub fn map_lists(mut self, map_lists: bool) -> Self {
+        self.map_lists = map_lists;
+        self
+    }
+
+    /// Sets how duplicate keys are handled when (de)serializing map-like
+    /// `list`s. See [`Options::map_lists`].
+    pub fn duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+
+    /// When deserializing, match `enum` and `variant` case names and `record`
+    /// field names ignoring ASCII case, e.g. accept `"First"` for `first`.
+    ///
+    /// Exact matches take precedence; an input matching more than one name
+    /// is rejected as ambiguous.
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// When deserializing, accept `alias` for any `enum` or `variant` case or
+    /// `record` field named `name`, e.g. `"first_case"` for `first-case`.
+    ///
+    /// Exact matches take precedence; an input matching more than one name
+    /// is rejected as ambiguous.
+    pub fn alias(mut self, alias: impl Into<String>, name: impl Into<String>) -> Self {
+        self.aliases
+            .entry(alias.into())
+            .or_default()
+            .push(name.into());
+        self
+    }
+
+    /// Serialize `enum` cases as their declaration index, e.g. `1`, and
+    /// `variant` cases as `{"<index>": <payload>}`, e.g. `{"1": 2}`.
+    ///
+    /// When deserializing, indices are accepted as well as names. Only use
+    /// this where the WIT is pinned, as indices change when cases are added
+    /// or reordered.
+    pub fn index_discriminants(mut self, index_discriminants: bool) -> Self {
+        self.index_discriminants = index_discriminants;
+        self
+    }
+
+    /// Resolves an `enum` or `variant` case `name` like
+    /// [`Options::resolve_name`], additionally accepting a declaration index
+    /// per [`Options::index_discriminants`].
+    pub(crate) fn resolve_case<'n>(
+        &self,
+        name: &'n str,
+        declared: impl IntoIterator<Item = &'n str>,
+    ) -> Result<&'n str, String> {
+        if self.index_discriminants && !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit())
+        {
+            return name
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| declared.into_iter().nth(idx))
+                .ok_or_else(|| format!("unknown case index {name}"));
+        }
+        self.resolve_name(name, declared)
+    }
+
+    /// Resolves an input `name` to one of the `declared` names per
+    /// [`Options::case_insensitive`] and [`Options::alias`], returning `name`
+    /// itself if nothing matches.
+    pub(crate) fn resolve_name<'n>(
+        &self,
+        name: &'n str,
+        declared: impl IntoIterator<Item = &'n str>,
+    ) -> Result<&'n str, String> {
+        if !self.case_insensitive && self.aliases.is_empty() {
+            return Ok(name);
+        }
+        let declared = declared.into_iter().collect::<Vec<_>>();
+        if let Some(exact) = declared.iter().copied().find(|&declared| declared == name) {
+            return Ok(exact);
+        }
+        let aliases = self.aliases.get(name);
+        let mut found: Option<&str> = None;
+        for declared in declared {
+            let matches = (self.case_insensitive && declared.eq_ignore_ascii_case(name))
+                || aliases.is_some_and(|names| names.iter().any(|n| n == declared));
+            if !matches {
+                continue;
+            }
+            if let Some(prev) = found {
+                return Err(format!(
+                    "ambiguous name `{name}` matches both `{prev}` and `{declared}`"
+                ));
+            }
+            found = Some(declared);
+        }
+        Ok(found.unwrap_or(name))
+    }
+
+    /// Sets the [`CodecRegistry`] consulted for custom type mappings.
+    #[cfg(feature = "json")]
+    pub fn codecs(mut self, codecs: CodecRegistry) -> Self {
+        self.codecs = Some(codecs.into());
+        self
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn codec_for(&self, ty: &wasmtime::component::Type) -> Option<&dyn crate::Codec> {
+        self.codecs.as_ref()?.find(ty)
+    }
+
+    /// Sets the [`Limits`] enforced while deserializing, e.g. for untrusted
+    /// inputs.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// Returns true for `list<tuple<string, T>>`, which [`Options::map_lists`]
+/// represents as an object.
+pub(crate) fn is_map_list(list: &types::List) -> bool {
+    match list.ty() {
+        Type::Tuple(tuple) => {
+            let mut types = tuple.types();
+            types.len() == 2 && matches!(types.next(), Some(Type::String))
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn default_options() -> &'static Options {
+    static DEFAULT: OnceLock<Options> = OnceLock::new();
+    DEFAULT.get_or_init(Options::default)
+}
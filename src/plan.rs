@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use wasmtime::component::Type;
+
+/// A [`Type`] precompiled for repeated deserialization.
+///
+/// Building a plan walks the whole type once, caching nested types and
+/// building lookup tables for `record` fields and `variant` cases, which
+/// [`DeserializeValWith::with_plan`](crate::DeserializeValWith::with_plan)
+/// would otherwise redo for every value.
+pub struct TypePlan {
+    root: PlanNode,
+}
+
+impl TypePlan {
+    /// Builds a plan for the given [`Type`].
+    pub fn new(ty: &Type) -> Self {
+        Self {
+            root: PlanNode::new(ty.clone()),
+        }
+    }
+
+    /// Returns the [`Type`] this plan was built for.
+    pub fn ty(&self) -> &Type {
+        &self.root.ty
+    }
+
+    pub(crate) fn root(&self) -> &PlanNode {
+        &self.root
+    }
+}
+
+pub(crate) struct PlanNode {
+    pub(crate) ty: Type,
+    pub(crate) kind: PlanKind,
+}
+
+pub(crate) enum PlanKind {
+    Leaf,
+    List(Box<PlanNode>),
+    Record {
+        fields: Vec<PlanNode>,
+        index: HashMap<Box<str>, usize>,
+    },
+    Tuple(Vec<PlanNode>),
+    Variant {
+        cases: Vec<Option<PlanNode>>,
+        index: HashMap<Box<str>, usize>,
+    },
+    Option(Box<PlanNode>),
+    Result {
+        ok: Option<Box<PlanNode>>,
+        err: Option<Box<PlanNode>>,
+    },
+}
+
+impl PlanNode {
+    fn new(ty: Type) -> Self {
+        let kind = match &ty {
+            Type::List(list) => PlanKind::List(Box::new(Self::new(list.ty()))),
+            Type::Record(rec) => PlanKind::Record {
+                fields: rec.fields().map(|field| Self::new(field.ty)).collect(),
+                index: rec
+                    .fields()
+                    .enumerate()
+                    .map(|(idx, field)| (field.name.into(), idx))
+                    .collect(),
+            },
+            Type::Tuple(tuple) => PlanKind::Tuple(tuple.types().map(Self::new).collect()),
+            Type::Variant(var) => PlanKind::Variant {
+                cases: var.cases().map(|case| case.ty.map(Self::new)).collect(),
+                index: var
+                    .cases()
+                    .enumerate()
+                    .map(|(idx, case)| (case.name.into(), idx))
+                    .collect(),
+            },
+            Type::Option(opt) => PlanKind::Option(Box::new(Self::new(opt.ty()))),
+            Type::Result(res) => PlanKind::Result {
+                ok: res.ok().map(|ty| Box::new(Self::new(ty))),
+                err: res.err().map(|ty| Box::new(Self::new(ty))),
+            },
+            _ => PlanKind::Leaf,
+        };
+        Self { ty, kind }
+    }
+}
@@ -1,10 +1,19 @@
+use std::collections::{hash_map::Entry, HashMap};
+
 use serde::{
     ser::{self, SerializeMap, SerializeSeq, SerializeTuple},
     Serialize,
 };
-use wasmtime::component::Val;
+use wasmtime::component::{Flags, Val};
+
+use crate::{
+    options::{default_options, is_map_list},
+    CharRepr, DuplicateKeys, FlagsRepr, Options,
+};
 
 /// A [`serde::Serialize`] implementation for [`Val`]s.
+///
+/// See [`SerializeValWith`] for non-default [`Options`].
 pub struct SerializeVal<'a>(pub &'a Val);
 
 impl<'a> Serialize for SerializeVal<'a> {
@@ -12,7 +21,47 @@ impl<'a> Serialize for SerializeVal<'a> {
     where
         S: serde::Serializer,
     {
-        match self.0 {
+        SerializeValWith::new(self.0, default_options()).serialize(serializer)
+    }
+}
+
+/// Like [`SerializeVal`], with the given [`Options`].
+pub struct SerializeValWith<'a> {
+    val: &'a Val,
+    options: &'a Options,
+}
+
+impl<'a> SerializeValWith<'a> {
+    /// Serializes the given [`Val`] with the given [`Options`].
+    pub fn new(val: &'a Val, options: &'a Options) -> Self {
+        Self { val, options }
+    }
+
+    fn child<'b>(&self, val: &'b Val) -> SerializeValWith<'b>
+    where
+        'a: 'b,
+    {
+        SerializeValWith {
+            val,
+            options: self.options,
+        }
+    }
+}
+
+impl<'a> Serialize for SerializeValWith<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[cfg(feature = "json")]
+        if self.options.codecs.is_some() {
+            if let Some(codec) = self.options.codec_for(&self.val.ty()) {
+                let json = codec.encode(self.val).map_err(ser::Error::custom)?;
+                return json.serialize(serializer);
+            }
+        }
+
+        match self.val {
             Val::Bool(v) => serializer.serialize_bool(*v),
             Val::S8(v) => serializer.serialize_i8(*v),
             Val::U8(v) => serializer.serialize_u8(*v),
@@ -25,6 +74,11 @@ impl<'a> Serialize for SerializeVal<'a> {
             Val::U64(v) => serializer.serialize_u64(*v),
 
             Val::Float32(v) => match v.classify() {
+                std::num::FpCategory::Nan
+                    if self.options.nan_payloads && v.to_bits() != f32::NAN.to_bits() =>
+                {
+                    serializer.serialize_str(&format!("NaN:{:#010x}", v.to_bits()))
+                }
                 std::num::FpCategory::Nan => serializer.serialize_str("NaN"),
                 std::num::FpCategory::Infinite if v.is_sign_negative() => {
                     serializer.serialize_str("-Infinity")
@@ -33,6 +87,11 @@ impl<'a> Serialize for SerializeVal<'a> {
                 _ => serializer.serialize_f32(*v),
             },
             Val::Float64(v) => match v.classify() {
+                std::num::FpCategory::Nan
+                    if self.options.nan_payloads && v.to_bits() != f64::NAN.to_bits() =>
+                {
+                    serializer.serialize_str(&format!("NaN:{:#018x}", v.to_bits()))
+                }
                 std::num::FpCategory::Nan => serializer.serialize_str("NaN"),
                 std::num::FpCategory::Infinite if v.is_sign_negative() => {
                     serializer.serialize_str("-Infinity")
@@ -41,26 +100,66 @@ impl<'a> Serialize for SerializeVal<'a> {
                 _ => serializer.serialize_f64(*v),
             },
 
-            Val::Char(v) => serializer.serialize_char(*v),
+            Val::Char(v) => match self.options.chars {
+                CharRepr::String => serializer.serialize_char(*v),
+                CharRepr::CodePoint => serializer.serialize_u32(*v as u32),
+            },
             Val::String(v) => serializer.serialize_str(v),
 
+            Val::List(vlst) if self.options.map_lists && is_map_list(&vlst.ty()) => {
+                // Apply `duplicate_keys` as deserializing would, so the output
+                // never has duplicate keys.
+                let mut entries: Vec<(&str, &Val)> = Vec::with_capacity(vlst.len());
+                let mut positions = HashMap::new();
+                for v in vlst.iter() {
+                    let Val::Tuple(entry) = v else {
+                        return Err(ser::Error::custom("expected a tuple"));
+                    };
+                    let [Val::String(key), value] = entry.values() else {
+                        return Err(ser::Error::custom("expected a (string, _) tuple"));
+                    };
+                    match positions.entry(&**key) {
+                        Entry::Vacant(vacant) => {
+                            vacant.insert(entries.len());
+                            entries.push((&**key, value));
+                        }
+                        Entry::Occupied(occupied) => match self.options.duplicate_keys {
+                            DuplicateKeys::Error => {
+                                return Err(ser::Error::custom(format!("duplicate key `{key}`")))
+                            }
+                            DuplicateKeys::First => (),
+                            DuplicateKeys::Last => entries[*occupied.get()].1 = value,
+                        },
+                    }
+                }
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, &self.child(value))?;
+                }
+                map.end()
+            }
+
             Val::List(vlst) => {
                 let mut seq = serializer.serialize_seq(Some(vlst.len()))?;
                 for v in vlst.iter() {
-                    seq.serialize_element(&SerializeVal(v))?;
+                    seq.serialize_element(&self.child(v))?;
                 }
                 seq.end()
             }
 
             Val::Record(vrec) => {
+                let mut fields = vrec.fields().collect::<Vec<_>>();
+                if self.options.canonical {
+                    fields.sort_by(|(a, _), (b, _)| canonical_key_order(a, b));
+                }
                 let mut map = serializer.serialize_map(None)?;
-                for (name, v) in vrec.fields() {
+                for (name, v) in fields {
                     if let Val::Option(opt) = v {
-                        if opt.value().is_none() {
+                        if opt.value().is_none() && !self.options.explicit_none_fields {
                             continue;
                         }
                     }
-                    map.serialize_entry(name, &SerializeVal(v))?;
+                    map.serialize_entry(name, &self.child(v))?;
                 }
                 map.end()
             }
@@ -68,17 +167,33 @@ impl<'a> Serialize for SerializeVal<'a> {
             Val::Tuple(vtup) => {
                 let mut tup = serializer.serialize_tuple(vtup.values().len())?;
                 for v in vtup.values() {
-                    tup.serialize_element(&SerializeVal(v))?;
+                    tup.serialize_element(&self.child(v))?;
                 }
                 tup.end()
             }
 
+            Val::Variant(vvar) if self.options.index_discriminants => {
+                let ty = vvar.ty();
+                let idx = ty
+                    .cases()
+                    .position(|case| case.name == vvar.discriminant())
+                    .ok_or_else(|| ser::Error::custom("unknown variant case"))?;
+                self.single_entry_map(serializer, &idx.to_string(), vvar.payload())
+            }
             Val::Variant(vvar) => {
                 // Note: While it would be natural to `serialize_*_variant` below,
                 // they require a variant index which might not be stable.
-                single_entry_map(serializer, vvar.discriminant(), vvar.payload())
+                self.single_entry_map(serializer, vvar.discriminant(), vvar.payload())
             }
 
+            Val::Enum(venu) if self.options.index_discriminants => {
+                let ty = venu.ty();
+                let idx = ty
+                    .names()
+                    .position(|name| name == venu.discriminant())
+                    .ok_or_else(|| ser::Error::custom("unknown enum case"))?;
+                serializer.serialize_u32(idx as u32)
+            }
             // re: `serialize_unit_variant`: see `Val::Variant` arm comment above.
             Val::Enum(venu) => serializer.serialize_str(venu.discriminant()),
 
@@ -87,9 +202,9 @@ impl<'a> Serialize for SerializeVal<'a> {
                     if let Val::Option(_) = v {
                         // Serialize `Some::<Option<_>>` as `{"value": ...}` to
                         // avoid ambiguity in serde_json.
-                        single_entry_map(serializer, "value", Some(v))
+                        self.single_entry_map(serializer, "value", Some(v))
                     } else {
-                        serializer.serialize_some(&SerializeVal(v))
+                        serializer.serialize_some(&self.child(v))
                     }
                 } else {
                     serializer.serialize_none()
@@ -97,34 +212,72 @@ impl<'a> Serialize for SerializeVal<'a> {
             }
 
             Val::Result(vres) => match vres.value() {
-                Ok(maybe_val) => single_entry_map(serializer, "result", maybe_val),
-                Err(maybe_val) => single_entry_map(serializer, "error", maybe_val),
+                Ok(maybe_val) => self.single_entry_map(serializer, "result", maybe_val),
+                Err(maybe_val) => self.single_entry_map(serializer, "error", maybe_val),
             },
 
-            Val::Flags(vflg) => {
-                let mut seq = serializer.serialize_seq(None)?;
-                for flag in vflg.flags() {
-                    seq.serialize_element(flag)?;
+            Val::Flags(vflg) => match self.options.flags {
+                FlagsRepr::Names => {
+                    // Always list flags in declaration order.
+                    let set = vflg.flags().collect::<Vec<_>>();
+                    let mut seq = serializer.serialize_seq(Some(set.len()))?;
+                    for name in vflg.ty().names().filter(|name| set.contains(name)) {
+                        seq.serialize_element(name)?;
+                    }
+                    seq.end()
                 }
-                seq.end()
-            }
+                FlagsRepr::Object => {
+                    let set = vflg.flags().collect::<Vec<_>>();
+                    let mut names = vflg.ty().names().collect::<Vec<_>>();
+                    if self.options.canonical {
+                        names.sort_by(|a, b| canonical_key_order(a, b));
+                    }
+                    let mut map = serializer.serialize_map(Some(names.len()))?;
+                    for name in names {
+                        map.serialize_entry(name, &set.contains(&name))?;
+                    }
+                    map.end()
+                }
+                FlagsRepr::Bitmask => serializer.serialize_u64(flags_bitmask(vflg)?),
+            },
 
             Val::Resource(_) => Err(ser::Error::custom("cannot serialize resources")),
         }
     }
 }
 
-fn single_entry_map<S: serde::Serializer>(
-    serializer: S,
-    key: &str,
-    val: Option<&Val>,
-) -> Result<S::Ok, S::Error> {
-    let mut map = serializer.serialize_map(Some(1))?;
-    match val {
-        Some(v) => map.serialize_entry(key, &SerializeVal(v))?,
-        None => map.serialize_entry(key, &())?,
+impl<'a> SerializeValWith<'a> {
+    fn single_entry_map<S: serde::Serializer>(
+        &self,
+        serializer: S,
+        key: &str,
+        val: Option<&Val>,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        match val {
+            Some(v) => map.serialize_entry(key, &self.child(v))?,
+            None => map.serialize_entry(key, &())?,
+        }
+        map.end()
     }
-    map.end()
+}
+
+/// Orders object keys by their UTF-16 code units, as required by RFC 8785.
+fn canonical_key_order(a: &str, b: &str) -> std::cmp::Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+fn flags_bitmask<E: ser::Error>(vflg: &Flags) -> Result<u64, E> {
+    let names = vflg.ty().names().collect::<Vec<_>>();
+    if names.len() > 64 {
+        return Err(E::custom(
+            "cannot serialize more than 64 flags as a bitmask",
+        ));
+    }
+    Ok(vflg
+        .flags()
+        .filter_map(|flag| names.iter().position(|name| *name == flag))
+        .fold(0, |bits, idx| bits | (1 << idx)))
 }
 
 #[cfg(all(test, feature = "json"))]
@@ -146,6 +299,22 @@ mod tests {
         assert_val_json(Val::String("☃☃☃".into()), json!("☃☃☃"));
     }
 
+    #[test]
+    fn nan_payloads() {
+        let options = Options::new().nan_payloads(true);
+        let to_json = |val| serde_json::to_value(SerializeValWith::new(&val, &options));
+        assert_eq!(to_json(Val::Float32(f32::NAN)).unwrap(), json!("NaN"));
+        assert_eq!(
+            to_json(Val::Float32(f32::from_bits(0x7fc00001))).unwrap(),
+            json!("NaN:0x7fc00001")
+        );
+        assert_eq!(
+            to_json(Val::Float64(f64::from_bits(0xfff8000000000000))).unwrap(),
+            json!("NaN:0xfff8000000000000")
+        );
+        assert_val_json(Val::Float32(f32::from_bits(0x7fc00001)), json!("NaN"));
+    }
+
     fn assert_val_json(val: Val, json: serde_json::Value) {
         let serialized = serde_json::to_value(SerializeVal(&val)).unwrap();
         assert_eq!(serialized, json);
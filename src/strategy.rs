@@ -0,0 +1,126 @@
+use proptest::{prelude::*, strategy::Union};
+use wasmtime::component::{Type, Val};
+
+const MAX_LIST_LEN: usize = 8;
+
+/// Returns a [`Strategy`] generating arbitrary [`Val`]s of the given [`Type`].
+///
+/// Floats include infinities and NaN, and `list`s are kept short so that
+/// nested types stay small.
+///
+/// # Panics
+///
+/// Panics if the type contains resources (`own` or `borrow`), which can't be
+/// created without a store.
+pub fn arb_val(ty: &Type) -> BoxedStrategy<Val> {
+    match ty.clone() {
+        Type::Bool => any::<bool>().prop_map(Val::Bool).boxed(),
+        Type::S8 => any::<i8>().prop_map(Val::S8).boxed(),
+        Type::U8 => any::<u8>().prop_map(Val::U8).boxed(),
+        Type::S16 => any::<i16>().prop_map(Val::S16).boxed(),
+        Type::U16 => any::<u16>().prop_map(Val::U16).boxed(),
+        Type::S32 => any::<i32>().prop_map(Val::S32).boxed(),
+        Type::U32 => any::<u32>().prop_map(Val::U32).boxed(),
+        Type::S64 => any::<i64>().prop_map(Val::S64).boxed(),
+        Type::U64 => any::<u64>().prop_map(Val::U64).boxed(),
+        Type::Float32 => any::<f32>().prop_map(Val::Float32).boxed(),
+        Type::Float64 => any::<f64>().prop_map(Val::Float64).boxed(),
+        Type::Char => any::<char>().prop_map(Val::Char).boxed(),
+        Type::String => any::<String>().prop_map(|s| Val::String(s.into())).boxed(),
+        Type::List(list) => prop::collection::vec(arb_val(&list.ty()), 0..=MAX_LIST_LEN)
+            .prop_map(move |values| list.new_val(values.into()).unwrap())
+            .boxed(),
+        Type::Record(rec) => rec
+            .fields()
+            .map(|field| arb_val(&field.ty))
+            .collect::<Vec<_>>()
+            .prop_map(move |values| {
+                let values = rec.fields().map(|field| field.name).zip(values);
+                rec.new_val(values).unwrap()
+            })
+            .boxed(),
+        Type::Tuple(tuple) => tuple
+            .types()
+            .map(|ty| arb_val(&ty))
+            .collect::<Vec<_>>()
+            .prop_map(move |values| tuple.new_val(values.into()).unwrap())
+            .boxed(),
+        Type::Variant(var) => Union::new(var.cases().map(|case| {
+            let var = var.clone();
+            let name = case.name.to_string();
+            match case.ty {
+                Some(ty) => arb_val(&ty)
+                    .prop_map(move |v| var.new_val(&name, Some(v)).unwrap())
+                    .boxed(),
+                None => Just(())
+                    .prop_map(move |_| var.new_val(&name, None).unwrap())
+                    .boxed(),
+            }
+        }))
+        .boxed(),
+        Type::Enum(enum_) => {
+            let names = enum_.names().map(String::from).collect::<Vec<_>>();
+            prop::sample::select(names)
+                .prop_map(move |name| enum_.new_val(&name).unwrap())
+                .boxed()
+        }
+        Type::Option(opt) => prop::option::of(arb_val(&opt.ty()))
+            .prop_map(move |v| opt.new_val(v).unwrap())
+            .boxed(),
+        Type::Result(res) => {
+            let ok = arb_payload(res.ok().as_ref());
+            let err = arb_payload(res.err().as_ref());
+            prop_oneof![ok.prop_map(Ok), err.prop_map(Err)]
+                .prop_map(move |v| res.new_val(v).unwrap())
+                .boxed()
+        }
+        Type::Flags(flags) => prop::collection::vec(any::<bool>(), flags.names().len())
+            .prop_map(move |set| {
+                let names = flags
+                    .names()
+                    .zip(set)
+                    .filter_map(|(name, set)| set.then_some(name))
+                    .collect::<Vec<_>>();
+                flags.new_val(&names).unwrap()
+            })
+            .boxed(),
+        Type::Own(_) | Type::Borrow(_) => panic!("cannot generate resources"),
+    }
+}
+
+fn arb_payload(ty: Option<&Type>) -> BoxedStrategy<Option<Val>> {
+    match ty {
+        Some(ty) => arb_val(ty).prop_map(Some).boxed(),
+        None => Just(None).boxed(),
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use proptest::test_runner::TestRunner;
+
+    use super::*;
+    use crate::{from_json, to_json};
+
+    #[test]
+    fn basic_types_round_trip() {
+        let mut runner = TestRunner::default();
+        for ty in [
+            Val::Bool(true).ty(),
+            Val::S64(0).ty(),
+            Val::U64(0).ty(),
+            Val::Float32(0.0).ty(),
+            Val::Float64(0.0).ty(),
+            Val::Char('x').ty(),
+            Val::String("".into()).ty(),
+        ] {
+            runner
+                .run(&arb_val(&ty), |val| {
+                    let json = to_json(&val).unwrap();
+                    prop_assert_eq!(from_json(&ty, json).unwrap(), val);
+                    Ok(())
+                })
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::io::{self, BufRead};
+
+use serde::de::{self, DeserializeSeed};
+use wasmtime::component::{Type, Val};
+
+use crate::{options::default_options, DeserializeValWith, LimitExceeded, LimitKind, Options};
+
+/// An iterator over the elements of a top-level JSON array, deserialized as a
+/// `list<T>` one element at a time from a reader.
+///
+/// Only the current element is buffered, so arbitrarily large lists can be
+/// processed, e.g. in batches. [`Limits`](crate::Limits) apply to each
+/// element separately, except `max_list_len`, which applies to the whole
+/// list; set `max_element_len` to bound the buffer for untrusted input.
+/// Codecs and [`Options::map_lists`] aren't applied to the list itself.
+///
+/// Wrap unbuffered readers (e.g. a [`File`](std::fs::File)) in a
+/// [`BufReader`](std::io::BufReader).
+pub struct JsonListReader<R> {
+    reader: R,
+    ty: Type,
+    options: Options,
+    state: State,
+    len: usize,
+    buf: Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Start,
+    Elements,
+    Done,
+}
+
+impl<R: BufRead> JsonListReader<R> {
+    /// Returns an iterator over the elements of the given `list` [`Type`]
+    /// read from `reader`.
+    pub fn new(ty: &Type, reader: R) -> serde_json::Result<Self> {
+        Self::with_options(ty, reader, default_options())
+    }
+
+    /// Returns an iterator over the elements of the given `list` [`Type`]
+    /// read from `reader` with the given [`Options`].
+    pub fn with_options(ty: &Type, reader: R, options: &Options) -> serde_json::Result<Self> {
+        let Type::List(list) = ty else {
+            return Err(de::Error::custom(format!(
+                "expected a list type, got {ty:?}"
+            )));
+        };
+        Ok(Self {
+            reader,
+            ty: list.ty(),
+            options: options.clone(),
+            state: State::Start,
+            len: 0,
+            buf: vec![],
+        })
+    }
+
+    fn peek_byte(&mut self) -> serde_json::Result<Option<u8>> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buf) => return Ok(buf.first().copied()),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(serde_json::Error::io(err)),
+            }
+        }
+    }
+
+    fn next_byte(&mut self) -> serde_json::Result<Option<u8>> {
+        let b = self.peek_byte()?;
+        if b.is_some() {
+            self.reader.consume(1);
+        }
+        Ok(b)
+    }
+
+    /// Skips whitespace, returning the next byte without consuming it.
+    fn skip_whitespace(&mut self) -> serde_json::Result<Option<u8>> {
+        while let Some(b) = self.peek_byte()? {
+            if !b.is_ascii_whitespace() {
+                return Ok(Some(b));
+            }
+            self.reader.consume(1);
+        }
+        Ok(None)
+    }
+
+    /// Reads the opening `[`, returning false for an empty list.
+    fn start(&mut self) -> serde_json::Result<bool> {
+        match self.skip_whitespace()? {
+            Some(b'[') => self.reader.consume(1),
+            Some(b) => return Err(unexpected(b)),
+            None => return Err(de::Error::custom("EOF while parsing a list")),
+        }
+        if self.skip_whitespace()? == Some(b']') {
+            self.reader.consume(1);
+            self.end()?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Buffers the raw JSON of the next element, returning true if it is the
+    /// last one.
+    fn read_element(&mut self) -> serde_json::Result<bool> {
+        self.buf.clear();
+        let limit = self.options.limits.max_element_len;
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+        loop {
+            let Some(b) = self.next_byte()? else {
+                return Err(de::Error::custom("EOF while parsing a list"));
+            };
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if b == b'\\' {
+                    escaped = true;
+                } else if b == b'"' {
+                    in_string = false;
+                }
+            } else {
+                match b {
+                    b'"' => in_string = true,
+                    b'[' | b'{' => depth += 1,
+                    b']' | b'}' if depth > 0 => depth -= 1,
+                    b']' if depth == 0 => return Ok(true),
+                    b',' if depth == 0 => return Ok(false),
+                    _ => (),
+                }
+            }
+            if self.buf.len() >= limit {
+                return Err(de::Error::custom(LimitExceeded {
+                    kind: LimitKind::ElementLength,
+                    limit,
+                }));
+            }
+            self.buf.push(b);
+        }
+    }
+
+    /// Checks that nothing but whitespace follows the closing `]`.
+    fn end(&mut self) -> serde_json::Result<()> {
+        match self.skip_whitespace()? {
+            Some(b) => Err(unexpected(b)),
+            None => Ok(()),
+        }
+    }
+
+    fn next_element(&mut self) -> serde_json::Result<Option<Val>> {
+        if self.state == State::Start {
+            if !self.start()? {
+                return Ok(None);
+            }
+            self.state = State::Elements;
+        }
+        let last = self.read_element()?;
+
+        self.len += 1;
+        let limit = self.options.limits.max_list_len;
+        if self.len > limit {
+            return Err(de::Error::custom(LimitExceeded {
+                kind: LimitKind::ListLength,
+                limit,
+            }));
+        }
+
+        let mut d = serde_json::Deserializer::from_slice(&self.buf);
+        let val = DeserializeValWith::new(&self.ty, &self.options).deserialize(&mut d)?;
+        d.end()?;
+
+        if last {
+            self.end()?;
+            self.state = State::Done;
+        }
+        Ok(Some(val))
+    }
+}
+
+impl<R: BufRead> Iterator for JsonListReader<R> {
+    type Item = serde_json::Result<Val>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.state == State::Done {
+            return None;
+        }
+        let res = self.next_element();
+        if !matches!(res, Ok(Some(_))) {
+            self.state = State::Done;
+        }
+        res.transpose()
+    }
+}
+
+fn unexpected(b: u8) -> serde_json::Error {
+    de::Error::custom(format!(
+        "unexpected character {:?} while parsing a list",
+        b as char
+    ))
+}
@@ -0,0 +1,85 @@
+/// Formats an error message for an unknown name, suggesting the closest
+/// declared name if there is one, e.g.
+/// "unknown field `retrys`, did you mean `retries`?".
+pub(crate) fn unknown_name<'n>(
+    kind: &str,
+    name: &str,
+    declared: impl IntoIterator<Item = &'n str>,
+) -> String {
+    let declared = declared.into_iter().collect::<Vec<_>>();
+    // Allow roughly one edit per three characters.
+    let max_distance = (name.chars().count() / 3).max(1);
+    let closest = declared
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance);
+    match (closest, declared.as_slice()) {
+        (Some((_, closest)), _) => format!("unknown {kind} `{name}`, did you mean `{closest}`?"),
+        (None, []) => format!("unknown {kind} `{name}`, there are none"),
+        (None, declared) => {
+            let expected = declared
+                .iter()
+                .map(|name| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("unknown {kind} `{name}`, expected one of {expected}")
+        }
+    }
+}
+
+/// The number of single-character insertions, deletions, substitutions and
+/// adjacent transpositions needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // Rows for `a[..i - 2]`, `a[..i - 1]` and `a[..i]`
+    let mut prev2 = vec![0; b.len() + 1];
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut curr = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        curr[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                curr[j] = curr[j].min(prev2[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("retries", "retries"), 0);
+        assert_eq!(edit_distance("retrys", "retries"), 2);
+        assert_eq!(edit_distance("retires", "retries"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn unknown_name_messages() {
+        let fields = ["retries", "timeout"];
+        assert_eq!(
+            unknown_name("field", "retrys", fields),
+            "unknown field `retrys`, did you mean `retries`?"
+        );
+        assert_eq!(
+            unknown_name("field", "x", fields),
+            "unknown field `x`, expected one of `retries`, `timeout`"
+        );
+        assert_eq!(
+            unknown_name("flag", "x", []),
+            "unknown flag `x`, there are none"
+        );
+    }
+}
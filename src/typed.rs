@@ -0,0 +1,140 @@
+use serde::{de::Error as _, Deserialize, Serialize};
+use serde_json::Value;
+use wasmtime::component::{Type, Val};
+
+use crate::{
+    deserialize_val, options::default_options, validate::validate_descriptor, SerializeVal,
+    TypeDescriptor, ValidationError,
+};
+
+/// A self-describing value, serialized as
+/// `{"type": <descriptor>, "value": <value>}`.
+///
+/// A `TypedVal` can be deserialized without the component its [`Type`] came
+/// from; the value is kept in its JSON form, checked against the descriptor,
+/// until it is converted back into a [`Val`] with [`TypedVal::to_val`], given
+/// a [`Type`] matching the descriptor, or with [`TypedVal::decode`] (with the
+/// `build-types` feature).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawTypedVal")]
+pub struct TypedVal {
+    #[serde(rename = "type")]
+    pub ty: TypeDescriptor,
+    pub value: Value,
+}
+
+#[derive(Deserialize)]
+struct RawTypedVal {
+    #[serde(rename = "type")]
+    ty: TypeDescriptor,
+    value: Value,
+}
+
+impl TryFrom<RawTypedVal> for TypedVal {
+    type Error = ValidationError;
+
+    fn try_from(raw: RawTypedVal) -> Result<Self, Self::Error> {
+        let typed = Self {
+            ty: raw.ty,
+            value: raw.value,
+        };
+        typed.check()?;
+        Ok(typed)
+    }
+}
+
+impl TypedVal {
+    /// Creates a `TypedVal` from a [`Val`] and its [`Type`].
+    pub fn new(val: &Val) -> serde_json::Result<Self> {
+        Ok(Self {
+            ty: TypeDescriptor::from(&val.ty()),
+            value: serde_json::to_value(SerializeVal(val))?,
+        })
+    }
+
+    /// Converts this value into a [`Val`] of the given [`Type`], which must
+    /// match this value's type descriptor.
+    pub fn to_val(&self, ty: &Type) -> serde_json::Result<Val> {
+        if !self.ty.describes(ty) {
+            return Err(serde_json::Error::custom(format!(
+                "type mismatch: value has type {:?}, expected {:?}",
+                self.ty,
+                TypeDescriptor::from(ty)
+            )));
+        }
+        deserialize_val(&self.value, ty)
+    }
+
+    /// Converts this value into a [`Val`] of a [`Type`] built from its
+    /// descriptor with [`TypeDescriptor::to_type`], so the original
+    /// component isn't needed.
+    #[cfg(feature = "build-types")]
+    pub fn decode(&self) -> serde_json::Result<Val> {
+        let ty = self.ty.to_type().map_err(serde_json::Error::custom)?;
+        deserialize_val(&self.value, &ty)
+    }
+
+    /// Checks that the value matches the type descriptor, without needing a
+    /// [`Type`], as [`validate_json`](crate::validate_json) would. This is
+    /// done automatically when deserializing.
+    pub fn check(&self) -> Result<(), ValidationError> {
+        validate_descriptor(&self.ty, &self.value, default_options()).map_err(|errors| {
+            let error = errors.into_iter().next().expect("at least one error");
+            ValidationError {
+                path: format!("/value{}", error.path),
+                message: error.message,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn primitive_typed_vals_json() {
+        assert_typed_json(Val::Bool(true), json!({"type": "bool", "value": true}));
+        assert_typed_json(Val::U16(123), json!({"type": "u16", "value": 123}));
+        assert_typed_json(
+            Val::Float64(f64::NAN),
+            json!({"type": "float64", "value": "NaN"}),
+        );
+        assert_typed_json(Val::Char('☃'), json!({"type": "char", "value": "☃"}));
+    }
+
+    #[test]
+    fn invalid_value() {
+        let invalid = |json| {
+            serde_json::from_value::<TypedVal>(json)
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(invalid(json!({"type": "u8", "value": 256})).contains("invalid value"));
+        assert!(invalid(json!({"type": "char", "value": "ab"})).contains("invalid type"));
+        // Checking agrees with `to_val` on what's accepted.
+        let err = invalid(json!({"type": "float64", "value": "NaN:0xzz"}));
+        assert!(err.contains("invalid value"), "{err}");
+        let err = invalid(json!({"type": {"enum": ["a"]}, "value": "b"}));
+        assert!(err.contains("unknown enum case"), "{err}");
+        let err = invalid(json!({"type": {"list": "bool"}, "value": [true, 1]}));
+        assert!(err.contains("/value/1"), "{err}");
+        assert!(serde_json::from_value::<TypedVal>(json!({"type": "u8", "value": "255"})).is_ok());
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let typed: TypedVal = serde_json::from_value(json!({"type": "u8", "value": 1})).unwrap();
+        assert!(typed.to_val(&Val::U16(0).ty()).is_err());
+    }
+
+    fn assert_typed_json(val: Val, json: serde_json::Value) {
+        let typed = TypedVal::new(&val).unwrap();
+        assert_eq!(serde_json::to_value(&typed).unwrap(), json);
+
+        let deserialized: TypedVal = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized.to_val(&val.ty()).unwrap(), val);
+    }
+}
@@ -0,0 +1,430 @@
+use std::{collections::HashMap, fmt};
+
+use serde_json::Value;
+use wasmtime::component::{Type, Val};
+
+use crate::{
+    deserialize_val_with, options::default_options, suggest::unknown_name, FlagsRepr, Options,
+    TypeDescriptor,
+};
+
+/// An error found by [`validate_json`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    /// A [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901) to the
+    /// invalid value.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Checks that a JSON value can be deserialized as the given [`Type`],
+/// reporting every problem found rather than just the first.
+///
+/// Compound values are checked without constructing a
+/// [`Val`](wasmtime::component::Val); primitives and values handled by a
+/// [`Codec`](crate::Codec) are checked by deserializing them.
+pub fn validate_json(ty: &Type, json: &Value) -> Result<(), Vec<ValidationError>> {
+    validate_json_with(ty, json, default_options())
+}
+
+/// Like [`validate_json`], with the given [`Options`].
+pub fn validate_json_with(
+    ty: &Type,
+    json: &Value,
+    options: &Options,
+) -> Result<(), Vec<ValidationError>> {
+    validate(ty, json, options)
+}
+
+/// Checks that a JSON value can be deserialized as a type matching the given
+/// [`TypeDescriptor`], as for [`validate_json_with`].
+pub(crate) fn validate_descriptor(
+    desc: &TypeDescriptor,
+    json: &Value,
+    options: &Options,
+) -> Result<(), Vec<ValidationError>> {
+    validate(&desc, json, options)
+}
+
+fn validate<S: Shape>(ty: &S, json: &Value, options: &Options) -> Result<(), Vec<ValidationError>> {
+    let mut validator = Validator {
+        options,
+        path: String::new(),
+        errors: vec![],
+    };
+    validator.check(ty, json);
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+/// A type that JSON values can be validated against: a [`Type`] or a
+/// [`TypeDescriptor`].
+trait Shape: Sized {
+    fn kind(&self) -> Kind<'_, Self>;
+
+    fn is_string(&self) -> bool;
+
+    fn option_payload(&self) -> Option<Self> {
+        match self.kind() {
+            Kind::Option(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    /// Checks a value with no compound [`Kind`] (or one handled by a codec)
+    /// by deserializing it.
+    fn check_leaf(&self, value: &Value, options: &Options) -> Result<(), String>;
+
+    fn has_codec(&self, options: &Options) -> bool;
+}
+
+enum Kind<'a, S> {
+    List(S),
+    Tuple(Vec<S>),
+    Record(Vec<(&'a str, S)>),
+    Variant(Vec<(&'a str, Option<S>)>),
+    Result(Option<S>, Option<S>),
+    Flags(Vec<&'a str>),
+    Option(S),
+    Leaf,
+}
+
+impl Shape for Type {
+    fn kind(&self) -> Kind<'_, Self> {
+        match self {
+            Type::List(list) => Kind::List(list.ty()),
+            Type::Tuple(tuple) => Kind::Tuple(tuple.types().collect()),
+            Type::Record(rec) => Kind::Record(rec.fields().map(|f| (f.name, f.ty)).collect()),
+            Type::Variant(var) => Kind::Variant(var.cases().map(|c| (c.name, c.ty)).collect()),
+            Type::Result(res) => Kind::Result(res.ok(), res.err()),
+            Type::Flags(flags) => Kind::Flags(flags.names().collect()),
+            Type::Option(opt) => Kind::Option(opt.ty()),
+            _ => Kind::Leaf,
+        }
+    }
+
+    fn is_string(&self) -> bool {
+        matches!(self, Type::String)
+    }
+
+    fn check_leaf(&self, value: &Value, options: &Options) -> Result<(), String> {
+        deserialize_val_with(value, self, options)
+            .map(drop)
+            .map_err(|err| err.to_string())
+    }
+
+    fn has_codec(&self, options: &Options) -> bool {
+        options.codec_for(self).is_some()
+    }
+}
+
+impl<'d> Shape for &'d TypeDescriptor {
+    fn kind(&self) -> Kind<'_, Self> {
+        use TypeDescriptor as D;
+
+        match *self {
+            D::List(ty) => Kind::List(&**ty),
+            D::Tuple(types) => Kind::Tuple(types.iter().collect()),
+            D::Record(fields) => Kind::Record(
+                fields
+                    .iter()
+                    .map(|field| (field.name.as_str(), &field.ty))
+                    .collect(),
+            ),
+            D::Variant(cases) => Kind::Variant(
+                cases
+                    .iter()
+                    .map(|case| (case.name.as_str(), case.ty.as_ref()))
+                    .collect(),
+            ),
+            D::Result { ok, err } => Kind::Result(ok.as_deref(), err.as_deref()),
+            D::Flags(names) => Kind::Flags(names.iter().map(String::as_str).collect()),
+            D::Option(ty) => Kind::Option(&**ty),
+            _ => Kind::Leaf,
+        }
+    }
+
+    fn is_string(&self) -> bool {
+        matches!(self, TypeDescriptor::String)
+    }
+
+    /// Without a [`Type`], only primitives can be deserialized directly;
+    /// `enum` cases and `flags` bitmasks are deserialized as their
+    /// underlying representation and then checked against the names.
+    fn check_leaf(&self, value: &Value, options: &Options) -> Result<(), String> {
+        use TypeDescriptor as D;
+
+        let deserialize =
+            |ty: &Type| deserialize_val_with(value, ty, options).map_err(|err| err.to_string());
+        let ty = match *self {
+            D::Bool => Type::Bool,
+            D::S8 => Type::S8,
+            D::U8 => Type::U8,
+            D::S16 => Type::S16,
+            D::U16 => Type::U16,
+            D::S32 => Type::S32,
+            D::U32 => Type::U32,
+            D::S64 => Type::S64,
+            D::U64 => Type::U64,
+            D::Float32 => Type::Float32,
+            D::Float64 => Type::Float64,
+            D::Char => Type::Char,
+            D::String => Type::String,
+            D::Enum(names) => {
+                let names = || names.iter().map(String::as_str);
+                if let Value::String(name) = value {
+                    let name = options.resolve_case(name, names())?;
+                    if !names().any(|n| n == name) {
+                        return Err(unknown_name("enum case", name, names()));
+                    }
+                    return Ok(());
+                }
+                let idx = match deserialize(&Type::U64)? {
+                    Val::U64(idx) if options.index_discriminants => idx,
+                    _ => return Err(format!("expected a case of {self:?}")),
+                };
+                return match usize::try_from(idx).ok().and_then(|idx| names().nth(idx)) {
+                    Some(_) => Ok(()),
+                    None => Err(format!("unknown case index {idx}")),
+                };
+            }
+            D::Flags(names) if options.flags == FlagsRepr::Bitmask => {
+                let Val::U64(bits) = deserialize(&Type::U64)? else {
+                    unreachable!("deserialized as u64");
+                };
+                return if names.len() >= 64 || bits >> names.len() == 0 {
+                    Ok(())
+                } else {
+                    Err(format!("invalid bitmask {bits} for {self:?}"))
+                };
+            }
+            D::Own | D::Borrow => return Err("resources can't be deserialized".into()),
+            _ => return Err(format!("expected a {self:?}")),
+        };
+        deserialize(&ty).map(drop)
+    }
+
+    fn has_codec(&self, _options: &Options) -> bool {
+        false
+    }
+}
+
+/// Returns the value type of a map-like list element type, i.e. `T` in
+/// `tuple<string, T>`.
+fn map_list_value<S: Shape>(elem: &S) -> Option<S> {
+    match elem.kind() {
+        Kind::Tuple(types) if types.len() == 2 && types[0].is_string() => types.into_iter().nth(1),
+        _ => None,
+    }
+}
+
+struct Validator<'a> {
+    options: &'a Options,
+    path: String,
+    errors: Vec<ValidationError>,
+}
+
+impl<'a> Validator<'a> {
+    fn check<S: Shape>(&mut self, ty: &S, value: &Value) {
+        if ty.has_codec(self.options) {
+            return self.check_leaf(ty, value);
+        }
+        match (ty.kind(), value) {
+            (Kind::List(elem), Value::Array(items)) => {
+                for (idx, item) in items.iter().enumerate() {
+                    self.nested(&idx.to_string(), |v| v.check(&elem, item));
+                }
+            }
+
+            (Kind::List(elem), Value::Object(obj)) if self.options.map_lists => {
+                let Some(value_ty) = map_list_value(&elem) else {
+                    return self.check_leaf(ty, value);
+                };
+                for (key, item) in obj {
+                    self.nested(key, |v| v.check(&value_ty, item));
+                }
+            }
+
+            (Kind::Tuple(types), Value::Array(items)) => {
+                if types.len() != items.len() {
+                    self.error(format!(
+                        "expected {} elements, found {}",
+                        types.len(),
+                        items.len()
+                    ));
+                    return;
+                }
+                for (idx, (ty, item)) in types.iter().zip(items).enumerate() {
+                    self.nested(&idx.to_string(), |v| v.check(ty, item));
+                }
+            }
+
+            (Kind::Record(fields), Value::Object(obj)) => {
+                let names = || fields.iter().map(|(name, _)| *name);
+                let mut present = HashMap::new();
+                for (key, item) in obj {
+                    match self.options.resolve_name(key, names()) {
+                        Err(message) => self.nested(key, |v| v.error(message)),
+                        Ok(name) if !names().any(|n| n == name) => {
+                            let message = unknown_name("field", key, names());
+                            self.nested(key, |v| v.error(message))
+                        }
+                        Ok(name) => {
+                            if present.insert(name, (key, item)).is_some() {
+                                self.nested(key, |v| v.error(format!("duplicate field `{name}`")))
+                            }
+                        }
+                    }
+                }
+                for (name, ty) in &fields {
+                    match present.get(name) {
+                        Some((key, item)) => self.nested(key, |v| v.check(ty, item)),
+                        None if ty.option_payload().is_some()
+                            && !self.options.require_all_fields => {}
+                        None => self.error(format!("missing field `{name}`")),
+                    }
+                }
+            }
+
+            (Kind::Variant(cases), Value::Object(obj)) if obj.len() == 1 => {
+                let (name, item) = obj.iter().next().unwrap();
+                let names = || cases.iter().map(|(name, _)| *name);
+                let resolved = match self.options.resolve_case(name, names()) {
+                    Ok(resolved) => resolved,
+                    Err(message) => return self.nested(name, |v| v.error(message)),
+                };
+                match cases.iter().find(|(name, _)| *name == resolved) {
+                    Some((_, ty)) => self.nested(name, |v| v.check_payload(ty.as_ref(), item)),
+                    None => {
+                        let message = unknown_name("variant", name, names());
+                        self.nested(name, |v| v.error(message))
+                    }
+                }
+            }
+
+            (Kind::Result(ok, err), Value::Object(obj)) if obj.len() == 1 => {
+                let (name, item) = obj.iter().next().unwrap();
+                match name.as_str() {
+                    "result" => self.nested(name, |v| v.check_payload(ok.as_ref(), item)),
+                    "error" => self.nested(name, |v| v.check_payload(err.as_ref(), item)),
+                    _ => self.nested(name, |v| v.error(format!("unknown variant `{name}`"))),
+                }
+            }
+
+            (Kind::Flags(names), Value::Array(items)) if self.options.flags == FlagsRepr::Names => {
+                for (idx, item) in items.iter().enumerate() {
+                    match item.as_str() {
+                        Some(name) if names.contains(&name) => (),
+                        Some(name) => {
+                            let message = unknown_name("flag", name, names.iter().copied());
+                            self.nested(&idx.to_string(), |v| v.error(message))
+                        }
+                        None => self
+                            .nested(&idx.to_string(), |v| v.error("expected a flag name".into())),
+                    }
+                }
+            }
+
+            (Kind::Flags(names), Value::Object(obj)) if self.options.flags == FlagsRepr::Object => {
+                for (key, item) in obj {
+                    if !names.contains(&key.as_str()) {
+                        let message = unknown_name("flag", key, names.iter().copied());
+                        self.nested(key, |v| v.error(message))
+                    } else if !item.is_boolean() {
+                        self.nested(key, |v| v.error("expected a bool".into()))
+                    }
+                }
+            }
+
+            (Kind::Option(_), Value::Null) => (),
+
+            (Kind::Option(inner), _) if inner.option_payload().is_none() => {
+                self.check(&inner, value)
+            }
+
+            (Kind::Option(inner), _) => match value.as_object() {
+                Some(obj) if obj.len() == 1 && obj.contains_key("value") => {
+                    self.nested("value", |v| v.check(&inner, &obj["value"]))
+                }
+                // Anything else is `some(some(...))` of the innermost type.
+                _ if self.options.lenient_nested_options => {
+                    let mut innermost = inner;
+                    while let Some(ty) = innermost.option_payload() {
+                        innermost = ty;
+                    }
+                    self.check(&innermost, value)
+                }
+                _ => self.error("expected an object with a single `value` field".into()),
+            },
+
+            _ => self.check_leaf(ty, value),
+        }
+    }
+
+    fn check_payload<S: Shape>(&mut self, ty: Option<&S>, value: &Value) {
+        match ty {
+            Some(ty) => self.check(ty, value),
+            None if value.is_null() => (),
+            None => self.error("expected null".into()),
+        }
+    }
+
+    /// Checks a primitive value, or one handled by a codec, by deserializing
+    /// it, reporting only the first problem found.
+    fn check_leaf<S: Shape>(&mut self, ty: &S, value: &Value) {
+        if let Err(message) = ty.check_leaf(value, self.options) {
+            self.error(message);
+        }
+    }
+
+    fn nested(&mut self, segment: &str, f: impl FnOnce(&mut Self)) {
+        let len = self.path.len();
+        self.path.push('/');
+        self.path
+            .push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        f(self);
+        self.path.truncate(len);
+    }
+
+    fn error(&mut self, message: String) {
+        self.errors.push(ValidationError {
+            path: self.path.clone(),
+            message,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wasmtime::component::Val;
+
+    use super::*;
+
+    #[test]
+    fn primitive_leaf_values() {
+        assert!(validate_json(&Val::U8(0).ty(), &json!(255)).is_ok());
+        assert!(validate_json(&Val::Float64(0.0).ty(), &json!("NaN")).is_ok());
+
+        let errors = validate_json(&Val::U8(0).ty(), &json!(256)).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "");
+
+        assert!(validate_json(&Val::Bool(true).ty(), &json!("true")).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Built-in [`Codec`]s for well-known WASI types.
+//!
+//! WASI types are matched structurally, so these codecs also apply to any
+//! other type with the same shape.
+
+#[cfg(feature = "wasi-http")]
+use serde_json::Map;
+use serde_json::Value;
+use wasmtime::{
+    component::{Type, Val},
+    Error, Result,
+};
+
+#[cfg(feature = "wasi-clocks")]
+use crate::FieldDescriptor;
+use crate::{Codec, CodecRegistry, TypeDescriptor};
+
+impl CodecRegistry {
+    /// Registers the built-in codecs for WASI types enabled by this crate's
+    /// `wasi-*` features.
+    pub fn wasi(self) -> Self {
+        let mut registry = self;
+        #[cfg(feature = "wasi-clocks")]
+        {
+            registry = registry.register(WasiDatetime::descriptor(), WasiDatetime);
+        }
+        #[cfg(feature = "wasi-http")]
+        {
+            registry = registry.register(WasiHttpFields::descriptor(), WasiHttpFields);
+        }
+        #[cfg(feature = "wasi-filesystem")]
+        {
+            registry = registry.register(WasiDescriptorFlags::descriptor(), WasiDescriptorFlags);
+        }
+        registry
+    }
+}
+
+/// Maps `wasi:clocks/wall-clock.datetime` to an RFC 3339 string, e.g.
+/// `"2023-09-25T12:00:00.500Z"`.
+#[cfg(feature = "wasi-clocks")]
+pub struct WasiDatetime;
+
+#[cfg(feature = "wasi-clocks")]
+impl WasiDatetime {
+    /// `record datetime { seconds: u64, nanoseconds: u32 }`
+    pub fn descriptor() -> TypeDescriptor {
+        TypeDescriptor::Record(vec![
+            FieldDescriptor {
+                name: "seconds".into(),
+                ty: TypeDescriptor::U64,
+            },
+            FieldDescriptor {
+                name: "nanoseconds".into(),
+                ty: TypeDescriptor::U32,
+            },
+        ])
+    }
+}
+
+#[cfg(feature = "wasi-clocks")]
+impl Codec for WasiDatetime {
+    fn encode(&self, val: &Val) -> Result<Value> {
+        use chrono::{DateTime, SecondsFormat, Utc};
+
+        let (Some(Val::U64(seconds)), Some(Val::U32(nanoseconds))) =
+            (field(val, "seconds"), field(val, "nanoseconds"))
+        else {
+            return Err(Error::msg("expected a datetime record"));
+        };
+        let datetime = DateTime::<Utc>::from_timestamp(i64::try_from(*seconds)?, *nanoseconds)
+            .ok_or_else(|| Error::msg("datetime out of range"))?;
+        Ok(datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true).into())
+    }
+
+    fn decode(&self, ty: &Type, json: Value) -> Result<Val> {
+        let Type::Record(rec) = ty else {
+            return Err(Error::msg("expected a record type"));
+        };
+        let s = json
+            .as_str()
+            .ok_or_else(|| Error::msg("expected an RFC 3339 string"))?;
+        let datetime = chrono::DateTime::<chrono::FixedOffset>::parse_from_rfc3339(s)?;
+        let seconds = u64::try_from(datetime.timestamp())
+            .map_err(|_| Error::msg("datetime before the Unix epoch"))?;
+        let nanoseconds = datetime.timestamp_subsec_nanos();
+        if nanoseconds >= 1_000_000_000 {
+            return Err(Error::msg("leap seconds are not supported"));
+        }
+        rec.new_val([
+            ("seconds", Val::U64(seconds)),
+            ("nanoseconds", Val::U32(nanoseconds)),
+        ])
+    }
+}
+
+/// Maps `wasi:http` field entries (`list<tuple<field-key, field-value>>`) to
+/// a header object, e.g. `{"content-type": "text/plain", "set-cookie": ["a",
+/// "b"]}`.
+///
+/// Repeated fields become arrays, in order. Values that aren't UTF-8 are
+/// written as arrays of bytes, e.g. `{"x-bin": [255, 0]}`.
+///
+/// This matches any `list<tuple<string, list<u8>>>` when registered by
+/// [`WasiHttpFields::descriptor`]; use
+/// [`CodecRegistry::register_type`] to limit it to a specific type.
+#[cfg(feature = "wasi-http")]
+pub struct WasiHttpFields;
+
+#[cfg(feature = "wasi-http")]
+impl WasiHttpFields {
+    /// `list<tuple<string, list<u8>>>`
+    pub fn descriptor() -> TypeDescriptor {
+        TypeDescriptor::List(Box::new(TypeDescriptor::Tuple(vec![
+            TypeDescriptor::String,
+            TypeDescriptor::List(Box::new(TypeDescriptor::U8)),
+        ])))
+    }
+}
+
+#[cfg(feature = "wasi-http")]
+impl Codec for WasiHttpFields {
+    fn encode(&self, val: &Val) -> Result<Value> {
+        let Val::List(entries) = val else {
+            return Err(Error::msg("expected a list"));
+        };
+        let mut headers = Map::new();
+        for entry in entries.iter() {
+            let Val::Tuple(entry) = entry else {
+                return Err(Error::msg("expected a tuple"));
+            };
+            let [Val::String(name), Val::List(bytes)] = entry.values() else {
+                return Err(Error::msg("expected a field entry"));
+            };
+            let bytes = bytes
+                .iter()
+                .map(|b| match b {
+                    Val::U8(b) => Ok(*b),
+                    _ => Err(Error::msg("expected a u8")),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let value = match String::from_utf8(bytes) {
+                Ok(s) => Value::String(s),
+                Err(err) => err.into_bytes().into(),
+            };
+            headers
+                .entry(name.to_string())
+                .or_insert_with(|| Value::Array(vec![]))
+                .as_array_mut()
+                .unwrap()
+                .push(value);
+        }
+        // Only repeated fields are written as arrays of values.
+        for values in headers.values_mut() {
+            if let Value::Array(vals) = values {
+                if vals.len() == 1 {
+                    *values = vals.pop().unwrap();
+                }
+            }
+        }
+        Ok(Value::Object(headers))
+    }
+
+    fn decode(&self, ty: &Type, json: Value) -> Result<Val> {
+        let Type::List(list) = ty else {
+            return Err(Error::msg("expected a list type"));
+        };
+        let Type::Tuple(tuple) = list.ty() else {
+            return Err(Error::msg("expected a tuple type"));
+        };
+        let Some(Type::List(bytes_ty)) = tuple.types().nth(1) else {
+            return Err(Error::msg("expected a list<u8> type"));
+        };
+        let Value::Object(headers) = json else {
+            return Err(Error::msg("expected a header object"));
+        };
+        let mut entries = vec![];
+        for (name, value) in headers {
+            let values = match value {
+                Value::Array(values) if !values.iter().all(Value::is_number) => values,
+                value => vec![value],
+            };
+            for value in values {
+                let bytes = match value {
+                    Value::String(value) => value.into_bytes(),
+                    Value::Array(bytes) => bytes
+                        .iter()
+                        .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| Error::msg(format!("invalid bytes for `{name}`")))?,
+                    _ => {
+                        return Err(Error::msg(format!(
+                            "expected string or byte array values for `{name}`"
+                        )))
+                    }
+                };
+                let bytes = bytes_ty.new_val(bytes.into_iter().map(Val::U8).collect())?;
+                entries.push(tuple.new_val(Box::new([Val::String(name.as_str().into()), bytes]))?);
+            }
+        }
+        list.new_val(entries.into())
+    }
+}
+
+/// Maps `wasi:filesystem` `descriptor-flags` to an object with an entry for
+/// every flag, e.g. `{"read": true, "write": false, ...}`.
+#[cfg(feature = "wasi-filesystem")]
+pub struct WasiDescriptorFlags;
+
+#[cfg(feature = "wasi-filesystem")]
+impl WasiDescriptorFlags {
+    /// `flags descriptor-flags { read, write, file-integrity-sync, ... }`
+    pub fn descriptor() -> TypeDescriptor {
+        TypeDescriptor::Flags(
+            [
+                "read",
+                "write",
+                "file-integrity-sync",
+                "data-integrity-sync",
+                "requested-write-sync",
+                "mutate-directory",
+            ]
+            .map(String::from)
+            .into(),
+        )
+    }
+}
+
+#[cfg(feature = "wasi-filesystem")]
+impl Codec for WasiDescriptorFlags {
+    fn encode(&self, val: &Val) -> Result<Value> {
+        let Val::Flags(flags) = val else {
+            return Err(Error::msg("expected flags"));
+        };
+        let set = flags.flags().collect::<Vec<_>>();
+        Ok(Value::Object(
+            flags
+                .ty()
+                .names()
+                .map(|name| (name.to_string(), set.contains(&name).into()))
+                .collect(),
+        ))
+    }
+
+    fn decode(&self, ty: &Type, json: Value) -> Result<Val> {
+        let Type::Flags(flags) = ty else {
+            return Err(Error::msg("expected a flags type"));
+        };
+        let Value::Object(obj) = json else {
+            return Err(Error::msg("expected an object"));
+        };
+        let mut names = vec![];
+        for (name, set) in &obj {
+            if !flags.names().any(|n| n == name) {
+                return Err(Error::msg(format!("unknown flag `{name}`")));
+            }
+            match set {
+                Value::Bool(true) => names.push(name.as_str()),
+                Value::Bool(false) => (),
+                _ => return Err(Error::msg(format!("expected a bool for `{name}`"))),
+            }
+        }
+        flags.new_val(&names)
+    }
+}
+
+#[cfg(feature = "wasi-clocks")]
+fn field<'a>(val: &'a Val, name: &str) -> Option<&'a Val> {
+    match val {
+        Val::Record(rec) => rec.fields().find_map(|(n, v)| (n == name).then_some(v)),
+        _ => None,
+    }
+}
@@ -0,0 +1,172 @@
+use proptest::prelude::*;
+use serde::de::DeserializeSeed;
+use wasmtime::component::{Type, Val};
+use wasmtime_component_serde::{
+    arb_val, from_json, to_json, DeserializeValWith, Options, TypePlan,
+};
+
+mod common;
+use common::{component_type, get_type};
+
+const PRIMITIVES: &[&str] = &[
+    "bool", "s8", "u8", "s16", "u16", "s32", "u32", "s64", "u64", "float32", "float64", "char",
+    "string",
+];
+
+/// The shape of an arbitrary type, rendered into a WAT component type.
+#[derive(Clone, Debug)]
+enum Shape {
+    Primitive(&'static str),
+    List(Box<Shape>),
+    Tuple(Vec<Shape>),
+    Option(Box<Shape>),
+    Result(Option<Box<Shape>>, Option<Box<Shape>>),
+    Record(Vec<Shape>),
+    Variant(Vec<Option<Shape>>),
+    Enum(usize),
+    Flags(usize),
+}
+
+impl Shape {
+    fn to_type(&self) -> Type {
+        let mut defs = vec![];
+        let ty = self.render(&mut defs);
+        component_type(&defs.concat(), &ty)
+    }
+
+    fn render(&self, defs: &mut Vec<String>) -> String {
+        let def = match self {
+            Self::Primitive(name) => return name.to_string(),
+            Self::List(elem) => return format!("(list {})", elem.render(defs)),
+            Self::Tuple(elems) => {
+                let elems = elems.iter().map(|s| s.render(defs)).collect::<Vec<_>>();
+                return format!("(tuple {})", elems.join(" "));
+            }
+            Self::Option(inner) => return format!("(option {})", inner.render(defs)),
+            Self::Result(ok, err) => {
+                let ok = ok.as_ref().map(|s| s.render(defs));
+                let err = err.as_ref().map(|s| s.render(defs));
+                let ok = ok.unwrap_or_default();
+                let err = err.map(|s| format!("(error {s})")).unwrap_or_default();
+                return format!("(result {ok} {err})");
+            }
+            Self::Record(fields) => {
+                let fields = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, s)| format!(r#"(field "f{idx}" {})"#, s.render(defs)))
+                    .collect::<String>();
+                format!("(record {fields})")
+            }
+            Self::Variant(cases) => {
+                let cases = cases
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, s)| {
+                        let payload = s.as_ref().map(|s| s.render(defs)).unwrap_or_default();
+                        format!(r#"(case "c{idx}" {payload})"#)
+                    })
+                    .collect::<String>();
+                format!("(variant {cases})")
+            }
+            Self::Enum(len) => {
+                let names = (0..*len)
+                    .map(|idx| format!(r#""e{idx}""#))
+                    .collect::<Vec<_>>();
+                format!("(enum {})", names.join(" "))
+            }
+            Self::Flags(len) => {
+                let names = (0..*len)
+                    .map(|idx| format!(r#""f{idx}""#))
+                    .collect::<Vec<_>>();
+                format!("(flags {})", names.join(" "))
+            }
+        };
+        let n = defs.len();
+        defs.push(format!(
+            r#"(type $t{n} {def}) (export $n{n} "n{n}" (type $t{n}))"#
+        ));
+        format!("$n{n}")
+    }
+}
+
+fn arb_shape() -> impl Strategy<Value = Shape> {
+    let leaf = prop::sample::select(PRIMITIVES).prop_map(Shape::Primitive);
+    leaf.prop_recursive(3, 16, 3, |inner| {
+        prop_oneof![
+            inner.clone().prop_map(|s| Shape::List(Box::new(s))),
+            prop::collection::vec(inner.clone(), 1..=3).prop_map(Shape::Tuple),
+            inner.clone().prop_map(|s| Shape::Option(Box::new(s))),
+            (
+                prop::option::of(inner.clone()),
+                prop::option::of(inner.clone())
+            )
+                .prop_map(|(ok, err)| Shape::Result(ok.map(Box::new), err.map(Box::new))),
+            prop::collection::vec(inner.clone(), 1..=3).prop_map(Shape::Record),
+            prop::collection::vec(prop::option::of(inner), 1..=3).prop_map(Shape::Variant),
+            (1..=3usize).prop_map(Shape::Enum),
+            (1..=3usize).prop_map(Shape::Flags),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn test_types_round_trip(
+        val in prop::sample::select(vec![
+            "bools",
+            "sints",
+            "uints",
+            "floats",
+            "options",
+            "list-chars",
+            "list-strings",
+            "result-ok-only",
+            "result-err-only",
+            "result-no-payloads",
+            "result-both-payloads",
+            "record",
+            "variant",
+            "enum",
+            "flags",
+        ])
+        .prop_flat_map(|name| arb_val(&get_type(name)))
+    ) {
+        assert_round_trip(&val)?;
+    }
+
+    #[test]
+    fn test_nested_options_round_trip(
+        val in arb_val(&component_type("", "(option (option (option (option u8))))"))
+    ) {
+        assert_round_trip(&val)?;
+    }
+}
+
+proptest! {
+    // Each case compiles a new component, so keep the number of cases down.
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn test_arbitrary_types_round_trip(
+        val in arb_shape().prop_flat_map(|shape| arb_val(&shape.to_type()))
+    ) {
+        assert_round_trip(&val)?;
+    }
+}
+
+fn assert_round_trip(val: &Val) -> Result<(), TestCaseError> {
+    let ty = val.ty();
+    let json = to_json(val).unwrap();
+    let deserialized = from_json(&ty, &json).unwrap();
+    prop_assert_eq!(&deserialized, val, "json: {}", json);
+
+    let plan = TypePlan::new(&ty);
+    let options = Options::new();
+    let mut d = serde_json::Deserializer::from_str(&json);
+    let planned = DeserializeValWith::with_plan(&plan, &options)
+        .deserialize(&mut d)
+        .unwrap();
+    prop_assert_eq!(&planned, val);
+    Ok(())
+}
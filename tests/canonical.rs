@@ -0,0 +1,44 @@
+use wasmtime_component_serde::{from_json, to_canonical_json};
+
+mod common;
+use common::{component_type, get_type};
+
+#[test]
+fn test_canonical_json() {
+    let ty = component_type(
+        r#"(type $r0 (record (field "zeta" u8) (field "alpha" (option u8)) (field "mid" (list float32))))
+        (export $r "r" (type $r0))"#,
+        "$r",
+    );
+    assert_canonical(
+        &ty,
+        r#"{"zeta": 1, "mid": [1.10, 0.5, 1e21]}"#,
+        r#"{"mid":[1.1,0.5,1e+21],"zeta":1}"#,
+    );
+    assert_canonical(
+        &ty,
+        r#"{"zeta": 1, "alpha": 2, "mid": []}"#,
+        r#"{"alpha":2,"mid":[],"zeta":1}"#,
+    );
+
+    let ty = get_type("flags");
+    assert_canonical(&ty, r#"["write", "read"]"#, r#"["read","write"]"#);
+}
+
+#[cfg(feature = "sha2")]
+#[test]
+fn test_canonical_hash() {
+    use wasmtime_component_serde::canonical_hash;
+
+    let ty = get_type("flags");
+    let a = from_json(&ty, r#"["write", "read"]"#).unwrap();
+    let b = from_json(&ty, r#"["read", "write"]"#).unwrap();
+    assert_eq!(canonical_hash(&a).unwrap(), canonical_hash(&b).unwrap());
+}
+
+fn assert_canonical(ty: &wasmtime::component::Type, json: &str, expected: &str) {
+    let val = from_json(ty, json).unwrap();
+    let canonical = to_canonical_json(&val).unwrap();
+    assert_eq!(canonical, expected);
+    assert_eq!(from_json(ty, &canonical).unwrap(), val);
+}
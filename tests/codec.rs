@@ -0,0 +1,135 @@
+use serde_json::{json, Value};
+use wasmtime::component::{Type, Val};
+use wasmtime_component_serde::{
+    from_json_with, to_json_with, validate_json_with, Codec, CodecRegistry, FieldDescriptor,
+    Options, TypeDescriptor,
+};
+
+mod common;
+use common::component_type;
+
+/// Maps `record uuid { high: u64, low: u64 }` to a UUID string.
+struct Uuid;
+
+impl Codec for Uuid {
+    fn encode(&self, val: &Val) -> wasmtime::Result<Value> {
+        let Val::Record(rec) = val else {
+            return Err(wasmtime::Error::msg("expected a record"));
+        };
+        let mut hex = String::new();
+        for (_, v) in rec.fields() {
+            let Val::U64(v) = v else {
+                return Err(wasmtime::Error::msg("expected a u64"));
+            };
+            hex += &format!("{v:016x}");
+        }
+        Ok(json!(format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )))
+    }
+
+    fn decode(&self, ty: &Type, json: Value) -> wasmtime::Result<Val> {
+        let Type::Record(rec) = ty else {
+            return Err(wasmtime::Error::msg("expected a record type"));
+        };
+        let hex = json
+            .as_str()
+            .ok_or_else(|| wasmtime::Error::msg("expected a string"))?
+            .replace('-', "");
+        if hex.len() != 32 {
+            return Err(wasmtime::Error::msg("invalid uuid"));
+        }
+        let high = u64::from_str_radix(&hex[..16], 16)?;
+        let low = u64::from_str_radix(&hex[16..], 16)?;
+        rec.new_val([("high", Val::U64(high)), ("low", Val::U64(low))])
+    }
+}
+
+fn uuid_type() -> Type {
+    component_type(
+        r#"(type $t (record (field "high" u64) (field "low" u64))) (export $uuid "uuid" (type $t))"#,
+        "(list $uuid)",
+    )
+}
+
+#[test]
+fn test_structural_codec() {
+    let options = Options::new().codecs(CodecRegistry::new().register(uuid_descriptor(), Uuid));
+    assert_codec_round_trip(&uuid_type(), &options);
+}
+
+#[test]
+fn test_type_codec() {
+    let ty = uuid_type();
+    let Type::List(list) = &ty else {
+        unreachable!()
+    };
+    let options = Options::new().codecs(CodecRegistry::new().register_type(list.ty(), Uuid));
+    assert_codec_round_trip(&ty, &options);
+
+    // A structurally identical type from another component isn't matched.
+    let other = uuid_type();
+    let val = from_json_with(&other, r#"[{"high": 1, "low": 2}]"#, &options).unwrap();
+    assert_eq!(
+        to_json_with(&val, &options).unwrap(),
+        r#"[{"high":1,"low":2}]"#
+    );
+}
+
+#[test]
+fn test_option_codec() {
+    let ty = component_type(
+        r#"(type $t (record (field "high" u64) (field "low" u64))) (export $uuid "uuid" (type $t))"#,
+        "(tuple (option $uuid) (option (option $uuid)))",
+    );
+    let options = Options::new().codecs(CodecRegistry::new().register(uuid_descriptor(), Uuid));
+    let uuid = r#""00000000-0000-0001-0000-000000000002""#;
+    for json in [
+        format!("[{uuid},{{\"value\":{uuid}}}]"),
+        "[null,null]".to_string(),
+        r#"[null,{"value":null}]"#.to_string(),
+    ] {
+        let val = from_json_with(&ty, &json, &options).unwrap();
+        assert_eq!(to_json_with(&val, &options).unwrap(), json);
+    }
+
+    // Lenient nested options also decode the payload with the codec.
+    let lenient = options.clone().lenient_nested_options(true);
+    let val = from_json_with(&ty, &format!("[{uuid},{uuid}]"), &lenient).unwrap();
+    assert_eq!(
+        to_json_with(&val, &options).unwrap(),
+        format!("[{uuid},{{\"value\":{uuid}}}]")
+    );
+}
+
+fn uuid_descriptor() -> TypeDescriptor {
+    TypeDescriptor::Record(vec![
+        FieldDescriptor {
+            name: "high".into(),
+            ty: TypeDescriptor::U64,
+        },
+        FieldDescriptor {
+            name: "low".into(),
+            ty: TypeDescriptor::U64,
+        },
+    ])
+}
+
+fn assert_codec_round_trip(ty: &Type, options: &Options) {
+    let json = r#"["00000000-0000-0001-0000-000000000002"]"#;
+    let val = from_json_with(ty, json, options).unwrap();
+    assert_eq!(
+        val,
+        from_json_with(ty, r#"[{"high": 1, "low": 2}]"#, &Options::new()).unwrap()
+    );
+    assert_eq!(to_json_with(&val, options).unwrap(), json);
+
+    assert!(validate_json_with(ty, &serde_json::from_str(json).unwrap(), options).is_ok());
+    let errors = validate_json_with(ty, &json!(["nope"]), options).unwrap_err();
+    assert_eq!(errors[0].path, "/0");
+}
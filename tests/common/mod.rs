@@ -0,0 +1,67 @@
+#![allow(dead_code)]
+
+use std::sync::{Mutex, OnceLock};
+
+use wasmtime::{
+    component::{Component, Instance, Linker, Type},
+    Config, Engine, Store,
+};
+
+/// Returns the result type of the `tests/types.wasm` export with the given name.
+pub fn get_type(name: &str) -> Type {
+    static INSTANCE_AND_STORE: OnceLock<(Instance, Mutex<Store<()>>)> = OnceLock::new();
+    let (instance, store) = INSTANCE_AND_STORE.get_or_init(|| {
+        let engine = Engine::new(Config::new().wasm_component_model(true)).expect("engine");
+        let component = Component::from_file(&engine, "tests/types.wasm").expect("component");
+        let linker = Linker::new(&engine);
+        let mut store = Store::new(&engine, ());
+        let instance = linker
+            .instantiate(&mut store, &component)
+            .expect("instance");
+        (instance, Mutex::new(store))
+    });
+    let mut store = store.lock().unwrap();
+    let func = instance
+        .exports(&mut *store)
+        .root()
+        .func(name)
+        .unwrap_or_else(|| panic!("export func named {name:?}"));
+    func.results(&*store)[0].clone()
+}
+
+/// Returns a [`Type`] built from a WAT component type expression.
+///
+/// Named types (`record`, `variant`, `enum` and `flags`) must be declared and
+/// exported in `defs`, e.g.
+/// `(type $r0 (record (field "a" u8))) (export $r "r" (type $r0))`, and then
+/// referenced by their exported name in `ty`, e.g. `(list $r)`.
+pub fn component_type(defs: &str, ty: &str) -> Type {
+    // Wrapping the type in a tuple ensures the lifted function always
+    // returns its results indirectly through a single `i32` pointer.
+    let wat = format!(
+        r#"
+        (component
+            (core module $m
+                (memory (export "memory") 1)
+                (func (export "f") (result i32) unreachable)
+            )
+            (core instance $i (instantiate $m))
+            {defs}
+            (func (export "f") (result (tuple {ty} u32))
+                (canon lift (core func $i "f") (memory $i "memory"))
+            )
+        )
+        "#
+    );
+    let engine = Engine::new(Config::new().wasm_component_model(true)).expect("engine");
+    let component = Component::new(&engine, wat).expect("component");
+    let mut store = Store::new(&engine, ());
+    let instance = Linker::new(&engine)
+        .instantiate(&mut store, &component)
+        .expect("instance");
+    let func = instance.get_func(&mut store, "f").expect("export func f");
+    match &func.results(&store)[0] {
+        Type::Tuple(tuple) => tuple.types().next().unwrap(),
+        ty => panic!("unexpected result type {ty:?}"),
+    }
+}
@@ -0,0 +1,110 @@
+use serde_json::json;
+use wasmtime_component_serde::{from_json, TypeDescriptor, TypedVal};
+
+mod common;
+use common::get_type;
+
+#[test]
+fn test_descriptors() {
+    assert_descriptor("bools", json!({"tuple": ["bool", "bool"]}));
+    assert_descriptor("uints", json!({"tuple": ["u8", "u16", "u32", "u64"]}));
+    assert_descriptor("floats", json!({"tuple": ["float32", "float64"]}));
+    assert_descriptor(
+        "options",
+        json!({"tuple": [{"option": "u8"}, {"option": {"option": "s8"}}]}),
+    );
+    assert_descriptor("list-chars", json!({"list": "char"}));
+    assert_descriptor("result-ok-only", json!({"result": {"ok": "u8"}}));
+    assert_descriptor("result-err-only", json!({"result": {"err": "s8"}}));
+    assert_descriptor("result-no-payloads", json!({"result": {}}));
+    assert_descriptor(
+        "record",
+        json!({"record": [
+            {"name": "required", "type": "u8"},
+            {"name": "optional", "type": {"option": "u8"}},
+        ]}),
+    );
+    assert_descriptor(
+        "variant",
+        json!({"variant": [
+            {"name": "without-payload"},
+            {"name": "with-payload", "type": "u8"},
+        ]}),
+    );
+    assert_descriptor("enum", json!({"enum": ["first", "second"]}));
+    assert_descriptor("flags", json!({"flags": ["read", "write"]}));
+}
+
+#[test]
+fn test_typed_vals() {
+    let ty = get_type("record");
+    let val = from_json(&ty, r#"{"required": 1, "optional": 2}"#).unwrap();
+    let typed = TypedVal::new(&val).unwrap();
+    let json = serde_json::to_string(&typed).unwrap();
+
+    // No `Type` is needed to decode the envelope itself.
+    let decoded: TypedVal = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded.value, json!({"required": 1, "optional": 2}));
+    assert_eq!(decoded.to_val(&ty).unwrap(), val);
+
+    assert!(decoded.to_val(&get_type("variant")).is_err());
+
+    // With the `build-types` feature, the original `Type` isn't needed at all.
+    #[cfg(feature = "build-types")]
+    {
+        let decoded = decoded.decode().unwrap();
+        assert_eq!(TypeDescriptor::from(&decoded.ty()), typed.ty);
+        assert_eq!(
+            wasmtime_component_serde::to_json(&decoded).unwrap(),
+            wasmtime_component_serde::to_json(&val).unwrap()
+        );
+    }
+}
+
+#[cfg(feature = "build-types")]
+#[test]
+fn test_build_types() {
+    for name in [
+        "bools",
+        "sints",
+        "uints",
+        "floats",
+        "options",
+        "list-chars",
+        "list-strings",
+        "result-ok-only",
+        "result-err-only",
+        "result-no-payloads",
+        "result-both-payloads",
+        "record",
+        "variant",
+        "enum",
+        "flags",
+    ] {
+        let desc = TypeDescriptor::from(&get_type(name));
+        let ty = desc
+            .to_type()
+            .unwrap_or_else(|err| panic!("{name}: {err:?}"));
+        assert!(desc.describes(&ty), "{name}");
+    }
+
+    // Named types may be nested.
+    let desc: TypeDescriptor = serde_json::from_value(json!({"list": {"record": [
+        {"name": "inner", "type": {"variant": [{"name": "a"}, {"name": "b-c", "type": {"enum": ["x"]}}]}},
+    ]}}))
+    .unwrap();
+    assert!(desc.describes(&desc.to_type().unwrap()));
+
+    // Names are escaped rather than spliced into the component.
+    let desc = TypeDescriptor::Enum(vec![r#"a") (export "b"#.into()]);
+    assert!(desc.to_type().is_err());
+    assert!(TypeDescriptor::Own.to_type().is_err());
+}
+
+fn assert_descriptor(type_name: &str, json: serde_json::Value) {
+    let ty = get_type(type_name);
+    let desc = TypeDescriptor::from(&ty);
+    assert_eq!(serde_json::to_value(&desc).unwrap(), json);
+    let deserialized: TypeDescriptor = serde_json::from_value(json).unwrap();
+    assert!(deserialized.describes(&ty));
+}
@@ -0,0 +1,44 @@
+use serde_json::json;
+use wasmtime_component_serde::{deserialize_val, example_json, example_val};
+
+mod common;
+use common::{component_type, get_type};
+
+#[test]
+fn test_examples() {
+    assert_example("bools", json!([false, false]));
+    assert_example("floats", json!([0.0, 0.0]));
+    assert_example("options", json!([0, {"value": 0}]));
+    assert_example("list-chars", json!(["x"]));
+    assert_example("list-strings", json!(["string"]));
+    assert_example("result-ok-only", json!({"result": 0}));
+    assert_example("result-err-only", json!({"result": null}));
+    assert_example("record", json!({"required": 0, "optional": 0}));
+    assert_example("variant", json!({"without-payload": null}));
+    assert_example("enum", json!("first"));
+    assert_example("flags", json!(["read", "write"]));
+}
+
+#[test]
+fn test_resource_examples() {
+    let defs = r#"(type $r (resource (rep i32))) (export $res "res" (type $r))"#;
+    let example = |ty: &str| example_json(&component_type(defs, ty));
+    assert_eq!(example("(own $res)"), None);
+    assert_eq!(example("(tuple u8 (own $res))"), None);
+    assert_eq!(example("(list (own $res))"), Some(json!([])));
+    assert_eq!(example("(option (own $res))"), Some(json!(null)));
+    assert_eq!(
+        example("(result (own $res) (error u8))"),
+        Some(json!({"error": 0}))
+    );
+}
+
+fn assert_example(type_name: &str, expected: serde_json::Value) {
+    let ty = get_type(type_name);
+    let json = example_json(&ty).unwrap();
+    assert_eq!(json, expected, "{type_name}");
+    assert_eq!(
+        deserialize_val(&json, &ty).unwrap(),
+        example_val(&ty).unwrap()
+    );
+}
@@ -0,0 +1,112 @@
+use serde_json::json;
+use wasmtime::component::Type;
+use wasmtime_component_serde::{
+    deserialize_val_with, from_json_with, LimitExceeded, LimitKind, Limits, Options,
+};
+
+mod common;
+use common::{component_type, get_type};
+
+#[test]
+fn test_limits() {
+    let max_list_len = Limits {
+        max_list_len: 2,
+        ..Limits::default()
+    };
+    assert_within_limit("list-strings", json!(["a", "b"]), max_list_len);
+    assert_exceeds_limit(
+        "list-strings",
+        json!(["a", "b", "c"]),
+        max_list_len,
+        LimitKind::ListLength,
+    );
+
+    // `tuple<option<u8>, option<option<s8>>>` has the `u8` at depth 2 and
+    // the `s8` at depth 3.
+    let max_depth = Limits {
+        max_depth: 3,
+        ..Limits::default()
+    };
+    assert_within_limit("options", json!([1, {"value": null}]), max_depth);
+    assert_exceeds_limit(
+        "options",
+        json!([null, {"value": 1}]),
+        max_depth,
+        LimitKind::Depth,
+    );
+    assert_exceeds_limit(
+        "options",
+        json!([1, null]),
+        Limits {
+            max_depth: 2,
+            ..Limits::default()
+        },
+        LimitKind::Depth,
+    );
+
+    let max_values = Limits {
+        max_values: 3,
+        ..Limits::default()
+    };
+    assert_within_limit("list-strings", json!(["a", "b"]), max_values);
+    assert_exceeds_limit(
+        "list-strings",
+        json!(["a", "b", "c"]),
+        max_values,
+        LimitKind::Values,
+    );
+
+    let max_bytes = Limits {
+        max_bytes: 5,
+        ..Limits::default()
+    };
+    assert_within_limit("list-strings", json!(["abc", "de"]), max_bytes);
+    assert_exceeds_limit(
+        "list-strings",
+        json!(["abc", "def"]),
+        max_bytes,
+        LimitKind::Bytes,
+    );
+
+    // Arrays of `u8` count like byte strings.
+    let bytes = component_type("", "(list u8)");
+    assert_within_limit_ty(&bytes, json!([1, 2, 3, 4, 5]), max_bytes);
+    assert_exceeds_limit_ty(
+        &bytes,
+        json!([1, 2, 3, 4, 5, 6]),
+        max_bytes,
+        LimitKind::Bytes,
+    );
+}
+
+#[test]
+fn test_limit_exceeded_not_spoofed() {
+    let message = "maximum nesting depth of 5 exceeded";
+    let options = Options::new();
+    let err = deserialize_val_with(&json!(message), &get_type("enum"), &options).unwrap_err();
+    assert!(err.to_string().contains(message), "{err}");
+    assert_eq!(LimitExceeded::from_error(&err), None);
+
+    let err = from_json_with(&get_type("enum"), &format!("{message:?}"), &options).unwrap_err();
+    assert_eq!(LimitExceeded::from_error(&err), None);
+}
+
+fn assert_within_limit(type_name: &str, json: serde_json::Value, limits: Limits) {
+    assert_within_limit_ty(&get_type(type_name), json, limits)
+}
+
+fn assert_within_limit_ty(ty: &Type, json: serde_json::Value, limits: Limits) {
+    let options = Options::new().limits(limits);
+    deserialize_val_with(&json, ty, &options).unwrap();
+}
+
+fn assert_exceeds_limit(type_name: &str, json: serde_json::Value, limits: Limits, kind: LimitKind) {
+    assert_exceeds_limit_ty(&get_type(type_name), json, limits, kind)
+}
+
+fn assert_exceeds_limit_ty(ty: &Type, json: serde_json::Value, limits: Limits, kind: LimitKind) {
+    let options = Options::new().limits(limits);
+    let err = deserialize_val_with(&json, ty, &options).unwrap_err();
+    let exceeded = LimitExceeded::from_error(&err).unwrap_or_else(|| panic!("{err}"));
+    assert_eq!(exceeded.kind, kind, "{err}");
+}
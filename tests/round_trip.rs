@@ -1,11 +1,12 @@
-use std::sync::{Mutex, OnceLock};
-
+use serde::de::{value::I64Deserializer, DeserializeSeed, IntoDeserializer};
 use serde_json::json;
-use wasmtime::{
-    component::{Component, Instance, Linker, Type},
-    Config, Engine, Store,
+use wasmtime_component_serde::{
+    deserialize_val, deserialize_val_with, serialize_val, serialize_val_with, validate_json_with,
+    CharRepr, DeserializeValWith, DuplicateKeys, FlagsRepr, Options, TypePlan,
 };
-use wasmtime_component_serde::{deserialize_val, serialize_val};
+
+mod common;
+use common::{component_type, get_type};
 
 #[test]
 fn test_round_trips() {
@@ -50,30 +51,282 @@ fn test_round_trips() {
     assert_round_trip("flags", json!(["read", "write"]));
 }
 
+#[test]
+fn test_lenient_nested_options() {
+    let ty = get_type("options");
+    assert!(deserialize_val(&json!([null, 2]), &ty).is_err());
+
+    let options = Options::new().lenient_nested_options(true);
+    for (input, expected) in [
+        (json!([null, null]), json!([null, null])),
+        (json!([null, 2]), json!([null, {"value": 2}])),
+        (
+            json!([null, {"value": null}]),
+            json!([null, {"value": null}]),
+        ),
+        (json!([null, {"value": 2}]), json!([null, {"value": 2}])),
+    ] {
+        let deserialized = deserialize_val_with(&input, &ty, &options).unwrap();
+        let serialized_json = serialize_val(serde_json::value::Serializer, &deserialized).unwrap();
+        assert_eq!(serialized_json, expected);
+    }
+}
+
+#[test]
+fn test_flags_reprs() {
+    let options = Options::new().flags(FlagsRepr::Object);
+    assert_round_trip_with("flags", json!({"read": false, "write": false}), &options);
+    assert_round_trip_with("flags", json!({"read": true, "write": true}), &options);
+    let deserialized = deserialize_val_with(&json!({"write": true}), &get_type("flags"), &options);
+    assert_eq!(
+        deserialized.unwrap(),
+        deserialize_val(&json!(["write"]), &get_type("flags")).unwrap()
+    );
+
+    let options = Options::new().flags(FlagsRepr::Bitmask);
+    assert_round_trip_with("flags", json!(0), &options);
+    assert_round_trip_with("flags", json!(2), &options);
+    assert_round_trip_with("flags", json!(3), &options);
+    assert!(deserialize_val_with(&json!(4), &get_type("flags"), &options).is_err());
+}
+
+#[test]
+fn test_char_code_points() {
+    let options = Options::new().chars(CharRepr::CodePoint);
+    assert_round_trip_with("list-chars", json!([0, 97, 0x2603, 0x10FFFF]), &options);
+    assert_same_val(
+        "list-chars",
+        json!([97, 0x2603]),
+        json!(["a", "☃"]),
+        &options,
+    );
+
+    let ty = get_type("list-chars");
+    assert!(deserialize_val_with(&json!([0xD800]), &ty, &options).is_err());
+    assert!(deserialize_val_with(&json!([0x110000]), &ty, &options).is_err());
+    assert!(deserialize_val_with(&json!([-1]), &ty, &options).is_err());
+    assert!(deserialize_val_with(&json!(["a"]), &ty, &options).is_err());
+    assert!(deserialize_val(&json!([97]), &ty).is_err());
+}
+
+#[test]
+fn test_none_fields() {
+    let options = Options::new().explicit_none_fields(true);
+    assert_round_trip_with("record", json!({"required": 1, "optional": null}), &options);
+    assert_round_trip_with("record", json!({"required": 1, "optional": 1}), &options);
+
+    let ty = get_type("record");
+    let options = Options::new().require_all_fields(true);
+    assert!(deserialize_val_with(&json!({"required": 1}), &ty, &options).is_err());
+    assert_eq!(
+        deserialize_val_with(&json!({"required": 1, "optional": null}), &ty, &options).unwrap(),
+        deserialize_val(&json!({"required": 1}), &ty).unwrap()
+    );
+}
+
+#[test]
+fn test_map_lists() {
+    let ty = component_type("", "(list (tuple string u32))");
+    let options = Options::new().map_lists(true);
+    let json = json!({"b": 1, "a": 2});
+    let deserialized = deserialize_val_with(&json, &ty, &options).unwrap();
+    assert_eq!(
+        deserialized,
+        deserialize_val(&json!([["b", 1], ["a", 2]]), &ty).unwrap()
+    );
+    let serialized_json =
+        serialize_val_with(serde_json::value::Serializer, &deserialized, &options).unwrap();
+    assert_eq!(serialized_json, json);
+    let plan = TypePlan::new(&ty);
+    let planned = DeserializeValWith::with_plan(&plan, &options)
+        .deserialize(&json)
+        .unwrap();
+    assert_eq!(planned, deserialized);
+
+    // Arrays of pairs are still accepted
+    let pairs = json!([["b", 1], ["a", 2]]);
+    assert_eq!(
+        deserialize_val_with(&pairs, &ty, &options).unwrap(),
+        deserialized
+    );
+    assert!(validate_json_with(&ty, &pairs, &options).is_ok());
+    assert!(validate_json_with(&ty, &json!([["b"]]), &options).is_err());
+
+    // Duplicate keys
+    let json = r#"{"a": 1, "b": 2, "a": 3}"#;
+    let from_json = |options: &Options| {
+        let mut d = serde_json::Deserializer::from_str(json);
+        deserialize_val_with(&mut d, &ty, options)
+    };
+    assert!(from_json(&options).is_err());
+    let first = from_json(&options.clone().duplicate_keys(DuplicateKeys::First)).unwrap();
+    assert_eq!(
+        first,
+        deserialize_val(&json!([["a", 1], ["b", 2]]), &ty).unwrap()
+    );
+    let last = from_json(&options.clone().duplicate_keys(DuplicateKeys::Last)).unwrap();
+    assert_eq!(
+        last,
+        deserialize_val(&json!([["a", 3], ["b", 2]]), &ty).unwrap()
+    );
+
+    // Serializing applies the same policy, so the output reads back as is.
+    let dupes = deserialize_val(&json!([["a", 1], ["b", 2], ["a", 3]]), &ty).unwrap();
+    let serialize =
+        |options: &Options| serialize_val_with(serde_json::value::Serializer, &dupes, options);
+    assert!(serialize(&options).is_err());
+    let options_first = options.clone().duplicate_keys(DuplicateKeys::First);
+    assert_eq!(serialize(&options_first).unwrap(), json!({"a": 1, "b": 2}));
+    let options_last = options.clone().duplicate_keys(DuplicateKeys::Last);
+    let serialized = serialize(&options_last).unwrap();
+    assert_eq!(serialized, json!({"a": 3, "b": 2}));
+    assert_eq!(
+        deserialize_val_with(&serialized, &ty, &options).unwrap(),
+        last
+    );
+
+    // Other lists are unaffected
+    assert_round_trip_with("list-strings", json!(["a", "b"]), &options);
+}
+
+#[test]
+fn test_index_discriminants() {
+    let options = Options::new().index_discriminants(true);
+    assert_round_trip_with("enum", json!(0), &options);
+    assert_round_trip_with("enum", json!(1), &options);
+    assert_round_trip_with("variant", json!({"0": null}), &options);
+    assert_round_trip_with("variant", json!({"1": 5}), &options);
+
+    // Names are still accepted
+    assert_same_val("enum", json!("second"), json!("second"), &options);
+    assert_same_val(
+        "variant",
+        json!({"with-payload": 5}),
+        json!({"with-payload": 5}),
+        &options,
+    );
+    assert_eq!(
+        deserialize_val_with(&json!("second"), &get_type("enum"), &options).unwrap(),
+        deserialize_val_with(&json!(1), &get_type("enum"), &options).unwrap()
+    );
+
+    // Formats may produce indices as signed integers
+    let ty = get_type("enum");
+    let from_i64 = |v: i64| {
+        let d: I64Deserializer<serde::de::value::Error> = v.into_deserializer();
+        DeserializeValWith::new(&ty, &options).deserialize(d)
+    };
+    assert_eq!(
+        from_i64(1).unwrap(),
+        deserialize_val_with(&json!(1), &ty, &options).unwrap()
+    );
+    assert!(from_i64(-1).is_err());
+
+    assert!(deserialize_val_with(&json!(2), &get_type("enum"), &options).is_err());
+    assert!(deserialize_val_with(&json!({"2": null}), &get_type("variant"), &options).is_err());
+    assert!(deserialize_val(&json!(1), &get_type("enum")).is_err());
+}
+
+#[test]
+fn test_name_matching() {
+    let options = Options::new().case_insensitive(true);
+    assert_same_val("enum", json!("First"), json!("first"), &options);
+    assert_same_val(
+        "variant",
+        json!({"With-Payload": 1}),
+        json!({"with-payload": 1}),
+        &options,
+    );
+    assert_same_val(
+        "record",
+        json!({"REQUIRED": 1}),
+        json!({"required": 1}),
+        &options,
+    );
+    assert!(deserialize_val(&json!("First"), &get_type("enum")).is_err());
+
+    let options = Options::new()
+        .alias("first_case", "first")
+        .alias("payload", "with-payload")
+        .alias("req", "required");
+    assert_same_val("enum", json!("first_case"), json!("first"), &options);
+    assert_same_val(
+        "variant",
+        json!({"payload": 1}),
+        json!({"with-payload": 1}),
+        &options,
+    );
+    assert_same_val(
+        "record",
+        json!({"req": 1}),
+        json!({"required": 1}),
+        &options,
+    );
+    let duplicate = json!({"req": 1, "required": 1});
+    assert!(deserialize_val_with(&duplicate, &get_type("record"), &options).is_err());
+
+    // An alias matching two cases is ambiguous
+    let options = Options::new()
+        .alias("either", "first")
+        .alias("either", "second");
+    let err = deserialize_val_with(&json!("either"), &get_type("enum"), &options).unwrap_err();
+    assert!(err.to_string().contains("ambiguous"), "{err}");
+
+    // ...unless the name is also declared, since exact matches come first
+    let ty = component_type(
+        r#"(type $t (enum "one" "two" "either")) (export $e "e" (type $t))"#,
+        "$e",
+    );
+    let options = options.alias("either", "one").alias("either", "two");
+    let val = deserialize_val_with(&json!("either"), &ty, &options).unwrap();
+    assert_eq!(val, deserialize_val(&json!("either"), &ty).unwrap());
+}
+
+#[test]
+fn test_unknown_name_suggestions() {
+    for (type_name, json, expected) in [
+        ("record", json!({"requird": 1}), "did you mean `required`?"),
+        (
+            "variant",
+            json!({"with-paylod": 1}),
+            "did you mean `with-payload`?",
+        ),
+        ("enum", json!("frist"), "did you mean `first`?"),
+        ("flags", json!(["raed"]), "did you mean `read`?"),
+        ("enum", json!("third"), "expected one of `first`, `second`"),
+    ] {
+        let err = deserialize_val(&json, &get_type(type_name)).unwrap_err();
+        assert!(err.to_string().contains(expected), "{err}");
+    }
+}
+
+fn assert_same_val(
+    type_name: &str,
+    json: serde_json::Value,
+    expected: serde_json::Value,
+    options: &Options,
+) {
+    let ty = get_type(type_name);
+    assert_eq!(
+        deserialize_val_with(&json, &ty, options).unwrap(),
+        deserialize_val(&expected, &ty).unwrap()
+    );
+}
+
 fn assert_round_trip(type_name: &str, json: serde_json::Value) {
+    assert_round_trip_with(type_name, json, &Options::new());
+}
+
+fn assert_round_trip_with(type_name: &str, json: serde_json::Value, options: &Options) {
     let ty = get_type(type_name);
-    let deserialized = deserialize_val(&json, &ty).unwrap();
-    let serialized_json = serialize_val(serde_json::value::Serializer, &deserialized).unwrap();
+    let deserialized = deserialize_val_with(&json, &ty, options).unwrap();
+    let serialized_json =
+        serialize_val_with(serde_json::value::Serializer, &deserialized, options).unwrap();
     assert_eq!(serialized_json, json);
-}
 
-fn get_type(name: &str) -> Type {
-    static INSTANCE_AND_STORE: OnceLock<(Instance, Mutex<Store<()>>)> = OnceLock::new();
-    let (instance, store) = INSTANCE_AND_STORE.get_or_init(|| {
-        let engine = Engine::new(Config::new().wasm_component_model(true)).expect("engine");
-        let component = Component::from_file(&engine, "tests/types.wasm").expect("component");
-        let linker = Linker::new(&engine);
-        let mut store = Store::new(&engine, ());
-        let instance = linker
-            .instantiate(&mut store, &component)
-            .expect("instance");
-        (instance, Mutex::new(store))
-    });
-    let mut store = store.lock().unwrap();
-    let func = instance
-        .exports(&mut *store)
-        .root()
-        .func(name)
-        .unwrap_or_else(|| panic!("export func named {name:?}"));
-    func.results(&*store)[0].clone()
+    let plan = TypePlan::new(&ty);
+    let planned = DeserializeValWith::with_plan(&plan, options)
+        .deserialize(&json)
+        .unwrap();
+    assert_eq!(planned, deserialized);
 }
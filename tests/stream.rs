@@ -0,0 +1,114 @@
+use serde_json::json;
+use wasmtime_component_serde::{
+    deserialize_val, from_json_reader, JsonListReader, LimitExceeded, LimitKind, Limits, Options,
+};
+
+mod common;
+use common::{component_type, get_type};
+
+#[test]
+fn test_from_json_reader() {
+    let ty = get_type("list-strings");
+    let json = br#" ["a", "b"] "#;
+    assert_eq!(
+        from_json_reader(&ty, &json[..]).unwrap(),
+        deserialize_val(&json!(["a", "b"]), &ty).unwrap()
+    );
+    assert!(from_json_reader(&ty, &br#"["a"] x"#[..]).is_err());
+
+    // Flag names can't be borrowed from a reader
+    let ty = get_type("flags");
+    assert_eq!(
+        from_json_reader(&ty, &br#"["read", "\u0077rite"]"#[..]).unwrap(),
+        deserialize_val(&json!(["read", "write"]), &ty).unwrap()
+    );
+    let ty = component_type(
+        r#"(type $f (flags "read" "write")) (export $flags "flags" (type $f))"#,
+        "(list $flags)",
+    );
+    let values = JsonListReader::new(&ty, &br#"[["read"], ["\u0077rite"]]"#[..])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(values.len(), 2);
+}
+
+#[test]
+fn test_list_reader() {
+    let ty = get_type("list-strings");
+    let json = br#" [ "a", "[,\"]" , "c"] "#;
+    let expected = deserialize_val(&json!(["a", "[,\"]", "c"]), &ty).unwrap();
+    assert_eq!(read_list("list-strings", json).unwrap(), expected);
+
+    assert_eq!(
+        read_list("list-chars", r#"["☃", "]", "\"", "\\"]"#.as_bytes()).unwrap(),
+        deserialize_val(&json!(["☃", "]", "\"", "\\"]), &get_type("list-chars")).unwrap()
+    );
+    assert_eq!(
+        read_list("list-chars", b" [ ] ").unwrap(),
+        deserialize_val(&json!([]), &get_type("list-chars")).unwrap()
+    );
+
+    for invalid in [
+        "",
+        "{}",
+        r#"["a""#,
+        r#"["a",]"#,
+        r#"[,"a"]"#,
+        r#"["a" "b"]"#,
+        "[1]",
+        r#"["a"] x"#,
+    ] {
+        assert!(
+            read_list("list-strings", invalid.as_bytes()).is_err(),
+            "{invalid}"
+        );
+    }
+}
+
+#[test]
+fn test_list_reader_elements() {
+    let ty = get_type("list-strings");
+    let mut reader = JsonListReader::new(&ty, &br#"["a", 1, "c"]"#[..]).unwrap();
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+
+    let options = Options::new().limits(Limits {
+        max_list_len: 2,
+        ..Limits::default()
+    });
+    let reader = JsonListReader::with_options(&ty, &br#"["a", "b", "c"]"#[..], &options).unwrap();
+    let results = reader.collect::<Vec<_>>();
+    assert_eq!(results.len(), 3);
+    let err = results[2].as_ref().unwrap_err().to_string();
+    assert!(err.contains("list length"), "{err}");
+
+    assert!(JsonListReader::new(&get_type("record"), &b"[]"[..]).is_err());
+
+    // Buffering stops at `max_element_len`
+    let options = Options::new().limits(Limits {
+        max_element_len: 8,
+        ..Limits::default()
+    });
+    let read = |json: &'static str| {
+        JsonListReader::with_options(&ty, json.as_bytes(), &options)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+    };
+    assert_eq!(read(r#"["abcdef"]"#).unwrap().len(), 1);
+    for json in [r#"["abcdefg"]"#, r#"["abcdefghijkl"#, "[[[[[[[[[[[["] {
+        let err = read(json).unwrap_err();
+        let exceeded = LimitExceeded::from_error(&err).unwrap_or_else(|| panic!("{err}"));
+        assert_eq!(exceeded.kind, LimitKind::ElementLength);
+    }
+}
+
+fn read_list(type_name: &str, json: &[u8]) -> serde_json::Result<wasmtime::component::Val> {
+    let ty = get_type(type_name);
+    let values = JsonListReader::new(&ty, json)?.collect::<Result<Vec<_>, _>>()?;
+    match ty {
+        wasmtime::component::Type::List(list) => Ok(list.new_val(values.into()).unwrap()),
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,76 @@
+use serde_json::json;
+use wasmtime_component_serde::{
+    validate_json, validate_json_with, FlagsRepr, Options, ValidationError,
+};
+
+mod common;
+use common::get_type;
+
+#[test]
+fn test_valid() {
+    assert_valid("record", json!({"required": 1}));
+    assert_valid("options", json!([1, {"value": null}]));
+    assert_valid("variant", json!({"with-payload": 1}));
+    assert_valid("result-both-payloads", json!({"error": -1}));
+    assert_valid("list-strings", json!(["a", "b"]));
+}
+
+#[test]
+fn test_collects_all_errors() {
+    assert_errors(
+        "record",
+        json!({"optional": 256, "unknown": true}),
+        &["/unknown", "", "/optional"],
+    );
+    assert_errors("list-chars", json!(["x", "yy", 1]), &["/1", "/2"]);
+    assert_errors("sints", json!([128, -1, "x"]), &[""]);
+    assert_errors("sints", json!([128, -1, "x", 1]), &["/0", "/2"]);
+    assert_errors("options", json!([-1, 1]), &["/0", "/1"]);
+    assert_errors(
+        "variant",
+        json!({"without-payload": 1}),
+        &["/without-payload"],
+    );
+    assert_errors("variant", json!({"unknown": 1}), &["/unknown"]);
+    assert_errors("enum", json!("third"), &[""]);
+    assert_errors("flags", json!(["read", "exec", 1]), &["/1", "/2"]);
+}
+
+#[test]
+fn test_options() {
+    let options = Options::new().flags(FlagsRepr::Object);
+    let errors = validate_json_with(
+        &get_type("flags"),
+        &json!({"read": 1, "exec": true}),
+        &options,
+    )
+    .unwrap_err();
+    let paths = errors.iter().map(|e| e.path.as_str()).collect::<Vec<_>>();
+    assert_eq!(paths, ["/exec", "/read"]);
+
+    let options = Options::new().lenient_nested_options(true);
+    let ty = get_type("options");
+    assert!(validate_json_with(&ty, &json!([1, 1]), &options).is_ok());
+    assert!(validate_json_with(&ty, &json!([1, {"value": 1}]), &options).is_ok());
+    let errors = validate_json_with(&ty, &json!([1, {"value": 128}]), &options).unwrap_err();
+    assert_eq!(errors[0].path, "/1/value");
+    let errors = validate_json_with(&ty, &json!([1, 128]), &options).unwrap_err();
+    assert_eq!(errors[0].path, "/1");
+}
+
+fn assert_valid(type_name: &str, json: serde_json::Value) {
+    let ty = get_type(type_name);
+    if let Err(errors) = validate_json(&ty, &json) {
+        panic!("{type_name}: unexpected errors: {errors:?}");
+    }
+}
+
+fn assert_errors(type_name: &str, json: serde_json::Value, paths: &[&str]) {
+    let ty = get_type(type_name);
+    let errors = validate_json(&ty, &json).unwrap_err();
+    let error_paths = errors
+        .iter()
+        .map(|e: &ValidationError| e.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(error_paths, paths, "{errors:?}");
+}
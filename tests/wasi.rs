@@ -0,0 +1,116 @@
+#![cfg(any(
+    feature = "wasi-clocks",
+    feature = "wasi-http",
+    feature = "wasi-filesystem"
+))]
+
+use serde_json::{json, Value};
+use wasmtime::component::Type;
+use wasmtime_component_serde::{
+    deserialize_val, deserialize_val_with, serialize_val_with, CodecRegistry, Options,
+};
+
+mod common;
+use common::component_type;
+
+#[cfg(feature = "wasi-clocks")]
+#[test]
+fn test_datetime() {
+    let ty = component_type(
+        r#"(type $t (record (field "seconds" u64) (field "nanoseconds" u32)))
+        (export $datetime "datetime" (type $t))"#,
+        "$datetime",
+    );
+    assert_wasi(
+        &ty,
+        json!({"seconds": 1695643200, "nanoseconds": 500_000_000}),
+        json!("2023-09-25T12:00:00.500Z"),
+    );
+    assert_wasi(
+        &ty,
+        json!({"seconds": 0, "nanoseconds": 0}),
+        json!("1970-01-01T00:00:00Z"),
+    );
+
+    // Offsets are normalized to UTC
+    let options = Options::new().codecs(CodecRegistry::new().wasi());
+    let val = deserialize_val_with(&json!("2023-09-25T14:00:00+02:00"), &ty, &options).unwrap();
+    assert_eq!(
+        val,
+        deserialize_val(&json!({"seconds": 1695643200, "nanoseconds": 0}), &ty).unwrap()
+    );
+    assert!(deserialize_val_with(&json!("1969-12-31T23:59:59Z"), &ty, &options).is_err());
+
+    // Option payloads are also written with the codec
+    let ty = component_type(
+        r#"(type $t (record (field "seconds" u64) (field "nanoseconds" u32)))
+        (export $datetime "datetime" (type $t))"#,
+        "(option $datetime)",
+    );
+    assert_wasi(
+        &ty,
+        json!({"seconds": 0, "nanoseconds": 0}),
+        json!("1970-01-01T00:00:00Z"),
+    );
+    assert_wasi(&ty, json!(null), json!(null));
+}
+
+#[cfg(feature = "wasi-http")]
+#[test]
+fn test_http_fields() {
+    let ty = component_type("", "(list (tuple string (list u8)))");
+    let text = |s: &str| json!(s.as_bytes());
+    assert_wasi(
+        &ty,
+        json!([
+            ["content-type", text("text/plain")],
+            ["set-cookie", text("a=1")],
+            ["set-cookie", text("b=2")],
+        ]),
+        json!({"content-type": "text/plain", "set-cookie": ["a=1", "b=2"]}),
+    );
+
+    // Values that aren't UTF-8 are written as bytes
+    assert_wasi(
+        &ty,
+        json!([
+            ["x-bin", [255, 0]],
+            ["x-mixed", [255]],
+            ["x-mixed", text("a")]
+        ]),
+        json!({"x-bin": [255, 0], "x-mixed": [[255], "a"]}),
+    );
+}
+
+#[cfg(feature = "wasi-filesystem")]
+#[test]
+fn test_descriptor_flags() {
+    let ty = component_type(
+        r#"(type $t (flags "read" "write" "file-integrity-sync" "data-integrity-sync"
+            "requested-write-sync" "mutate-directory"))
+        (export $descriptor-flags "descriptor-flags" (type $t))"#,
+        "$descriptor-flags",
+    );
+    assert_wasi(
+        &ty,
+        json!(["read", "mutate-directory"]),
+        json!({
+            "read": true,
+            "write": false,
+            "file-integrity-sync": false,
+            "data-integrity-sync": false,
+            "requested-write-sync": false,
+            "mutate-directory": true,
+        }),
+    );
+}
+
+/// Checks that a value written as `plain` with default options is written as
+/// `wasi` with the WASI codecs, and back.
+fn assert_wasi(ty: &Type, plain: Value, wasi: Value) {
+    let options = Options::new().codecs(CodecRegistry::new().wasi());
+    let val = deserialize_val(&plain, ty).unwrap();
+    let serialized = serialize_val_with(serde_json::value::Serializer, &val, &options).unwrap();
+    assert_eq!(serialized, wasi);
+    assert_eq!(deserialize_val_with(&wasi, ty, &options).unwrap(), val);
+}
@@ -0,0 +1,45 @@
+use serde_json::json;
+use wasmtime_component_serde::{
+    deserialize_val, to_canonical_json, to_canonical_json_writer, to_json, to_json_pretty,
+    to_json_pretty_with, to_json_vec, to_json_writer, to_json_writer_with, FlagsRepr, Options,
+};
+
+mod common;
+use common::get_type;
+
+#[test]
+fn test_writers() {
+    let val = deserialize_val(&json!({"required": 1, "optional": 2}), &get_type("record")).unwrap();
+    let json = to_json(&val).unwrap();
+
+    assert_eq!(to_json_vec(&val).unwrap(), json.as_bytes());
+
+    let mut buf = Vec::new();
+    to_json_writer(&mut buf, &val).unwrap();
+    assert_eq!(buf, json.as_bytes());
+
+    let mut buf = Vec::new();
+    to_canonical_json_writer(&mut buf, &val).unwrap();
+    assert_eq!(buf, to_canonical_json(&val).unwrap().as_bytes());
+
+    let flags = deserialize_val(&json!(["write"]), &get_type("flags")).unwrap();
+    let mut buf = Vec::new();
+    to_json_writer_with(&mut buf, &flags, &Options::new().flags(FlagsRepr::Bitmask)).unwrap();
+    assert_eq!(buf, b"2");
+}
+
+#[test]
+fn test_pretty() {
+    let val = deserialize_val(&json!({"required": 1, "optional": 2}), &get_type("record")).unwrap();
+    assert_eq!(
+        to_json_pretty(&val, "\t").unwrap(),
+        "{\n\t\"required\": 1,\n\t\"optional\": 2\n}"
+    );
+
+    let flags = deserialize_val(&json!(["read", "write"]), &get_type("flags")).unwrap();
+    let options = Options::new().flags(FlagsRepr::Object);
+    assert_eq!(
+        to_json_pretty_with(&flags, "  ", &options).unwrap(),
+        "{\n  \"read\": true,\n  \"write\": true\n}"
+    );
+}